@@ -0,0 +1,248 @@
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use datafusion::arrow::array::Int64Array;
+use datafusion::arrow::record_batch::RecordBatch;
+use datafusion::arrow::util::display::array_value_to_string;
+use datafusion::error::{DataFusionError, Result};
+use datafusion::prelude::*;
+use tracing::info;
+
+use crate::{register_tables, ForeignKey, Format, Tpc};
+
+/// Maximum number of violating rows to report per constraint
+const MAX_SAMPLES: usize = 5;
+
+/// Result of checking one primary key or foreign key constraint
+#[derive(Debug, Clone)]
+pub struct ConstraintCheck {
+    pub table: String,
+    /// Description of the constraint, such as `PRIMARY KEY (o_orderkey)`
+    pub constraint: String,
+    /// Number of duplicated keys for a primary key, or orphaned rows for a foreign key
+    pub violations: i64,
+    /// Some of the duplicated keys or orphaned rows
+    pub samples: Vec<String>,
+}
+
+/// Check that the primary keys of every table are unique and that every foreign key refers to
+/// an existing row. Rows with null foreign key columns are not violations.
+pub async fn check_constraints(
+    benchmark: &dyn Tpc,
+    data_path: &str,
+    format: Format,
+) -> Result<Vec<ConstraintCheck>> {
+    let ctx = SessionContext::new();
+    register_tables(&ctx, benchmark, data_path, format).await?;
+
+    let mut checks = vec![];
+    for table in benchmark.get_table_names() {
+        let primary_key = benchmark.get_primary_key(table)?.join(", ");
+        info!(table, "Checking primary key");
+        let sql = format!(
+            "SELECT {pk}, COUNT(*) AS row_count FROM {table} GROUP BY {pk} HAVING COUNT(*) > 1",
+            pk = primary_key,
+            table = table
+        );
+        checks.push(check(&ctx, table, format!("PRIMARY KEY ({})", primary_key), &sql).await?);
+
+        for foreign_key in benchmark.get_foreign_keys(table)? {
+            info!(
+                table,
+                referenced_table = %foreign_key.referenced_table,
+                "Checking foreign key"
+            );
+            checks.push(
+                check(
+                    &ctx,
+                    table,
+                    describe(&foreign_key),
+                    &orphans_query(table, &foreign_key),
+                )
+                .await?,
+            );
+        }
+    }
+    Ok(checks)
+}
+
+fn describe(foreign_key: &ForeignKey) -> String {
+    format!(
+        "FOREIGN KEY ({}) REFERENCES {} ({})",
+        foreign_key.columns.join(", "),
+        foreign_key.referenced_table,
+        foreign_key.referenced_columns.join(", ")
+    )
+}
+
+/// Query for the rows whose foreign key does not match any row in the referenced table
+fn orphans_query(table: &str, foreign_key: &ForeignKey) -> String {
+    let columns = foreign_key
+        .columns
+        .iter()
+        .map(|c| format!("f.{}", c))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let join = foreign_key
+        .columns
+        .iter()
+        .zip(&foreign_key.referenced_columns)
+        .map(|(c, r)| format!("f.{} = r.{}", c, r))
+        .collect::<Vec<_>>()
+        .join(" AND ");
+    let not_null = foreign_key
+        .columns
+        .iter()
+        .map(|c| format!("f.{} IS NOT NULL", c))
+        .collect::<Vec<_>>()
+        .join(" AND ");
+    format!(
+        "SELECT {} FROM {} f LEFT JOIN {} r ON {} WHERE {} AND r.{} IS NULL",
+        columns,
+        table,
+        foreign_key.referenced_table,
+        join,
+        not_null,
+        foreign_key.referenced_columns[0]
+    )
+}
+
+/// Count the rows returned by a query that finds violations, and collect some of them
+async fn check(
+    ctx: &SessionContext,
+    table: &str,
+    constraint: String,
+    sql: &str,
+) -> Result<ConstraintCheck> {
+    let count = ctx
+        .sql(&format!("SELECT COUNT(*) FROM ({})", sql))
+        .await?
+        .collect()
+        .await?;
+    let violations = count
+        .first()
+        .and_then(|batch| batch.column(0).as_any().downcast_ref::<Int64Array>())
+        .map(|array| array.value(0))
+        .ok_or_else(|| DataFusionError::Execution(format!("No count returned by: {}", sql)))?;
+
+    let mut samples = vec![];
+    if violations > 0 {
+        let batches = ctx
+            .sql(&format!("{} LIMIT {}", sql, MAX_SAMPLES))
+            .await?
+            .collect()
+            .await?;
+        for batch in &batches {
+            samples.extend(format_rows(batch)?);
+        }
+    }
+
+    Ok(ConstraintCheck {
+        table: table.to_owned(),
+        constraint,
+        violations,
+        samples,
+    })
+}
+
+fn format_rows(batch: &RecordBatch) -> Result<Vec<String>> {
+    let schema = batch.schema();
+    let mut rows = vec![];
+    for row in 0..batch.num_rows() {
+        let mut values = vec![];
+        for (field, column) in schema.fields().iter().zip(batch.columns()) {
+            values.push(format!(
+                "{}={}",
+                field.name(),
+                array_value_to_string(column, row)?
+            ));
+        }
+        rows.push(values.join(", "));
+    }
+    Ok(rows)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tpch::TpcH;
+    use std::fs;
+
+    #[tokio::test]
+    async fn finds_duplicate_keys_and_orphans() -> Result<()> {
+        let benchmark = TpcH::new();
+        let dir = tempfile::tempdir()?;
+        let data_path = dir.path().to_str().unwrap();
+        for table in benchmark.get_table_names() {
+            fs::create_dir(Format::Raw.table_path(&benchmark, data_path, table))?;
+        }
+        let nation = format!(
+            "{}{}{}",
+            include_str!("tpch/testdata/nation.tbl"),
+            "0|DUPLICATE|0|duplicated key|\n",
+            "25|ORPHAN|9|unknown region|\n"
+        );
+        let nation_path = Format::Raw.table_path(&benchmark, data_path, "nation");
+        fs::write(format!("{}/part-0.tbl", nation_path), nation)?;
+        let region_path = Format::Raw.table_path(&benchmark, data_path, "region");
+        fs::write(
+            format!("{}/part-0.tbl", region_path),
+            include_str!("tpch/testdata/region.tbl"),
+        )?;
+
+        let checks = check_constraints(&benchmark, data_path, Format::Raw).await?;
+        let find = |table: &str, constraint: &str| {
+            checks
+                .iter()
+                .find(|c| c.table == table && c.constraint == constraint)
+                .unwrap()
+        };
+
+        let primary_key = find("nation", "PRIMARY KEY (n_nationkey)");
+        assert_eq!(1, primary_key.violations);
+        assert_eq!(vec!["n_nationkey=0, row_count=2"], primary_key.samples);
+
+        let foreign_key = find(
+            "nation",
+            "FOREIGN KEY (n_regionkey) REFERENCES region (r_regionkey)",
+        );
+        assert_eq!(1, foreign_key.violations);
+        assert_eq!(vec!["n_regionkey=9"], foreign_key.samples);
+
+        let region = find("region", "PRIMARY KEY (r_regionkey)");
+        assert_eq!(0, region.violations);
+        assert!(region.samples.is_empty());
+
+        let others: i64 = checks
+            .iter()
+            .filter(|c| c.table != "nation")
+            .map(|c| c.violations)
+            .sum();
+        assert_eq!(0, others);
+        Ok(())
+    }
+
+    #[test]
+    fn orphans_query_matches_every_column() {
+        let foreign_key = ForeignKey::new(
+            &["l_partkey", "l_suppkey"],
+            "partsupp",
+            &["ps_partkey", "ps_suppkey"],
+        );
+        assert_eq!(
+            "SELECT f.l_partkey, f.l_suppkey FROM lineitem f LEFT JOIN partsupp r \
+             ON f.l_partkey = r.ps_partkey AND f.l_suppkey = r.ps_suppkey \
+             WHERE f.l_partkey IS NOT NULL AND f.l_suppkey IS NOT NULL AND r.ps_partkey IS NULL",
+            orphans_query("lineitem", &foreign_key)
+        );
+    }
+}
@@ -0,0 +1,229 @@
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::fmt::Write;
+
+use datafusion::arrow::record_batch::RecordBatch;
+use datafusion::arrow::util::display::{ArrayFormatter, FormatOptions};
+use datafusion::error::{DataFusionError, Result};
+use datafusion::prelude::*;
+use futures::StreamExt;
+use tracing::info;
+
+use crate::manifest::fnv1a;
+use crate::{register_tables, Format, Tpc};
+
+/// Number of rows and content checksum of a table
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TableChecksum {
+    pub rows: u64,
+    /// Sum of the hashes of all rows, which does not depend on the order of the rows or on how
+    /// they are split into files
+    pub checksum: u64,
+}
+
+/// Checksums of the same table in two data sets
+#[derive(Debug, Clone)]
+pub struct TableComparison {
+    pub table: String,
+    pub left: TableChecksum,
+    pub right: TableChecksum,
+}
+
+impl TableComparison {
+    pub fn is_match(&self) -> bool {
+        self.left == self.right
+    }
+}
+
+/// Compute the checksum of every table in a data set
+pub async fn table_checksums(
+    benchmark: &dyn Tpc,
+    data_path: &str,
+    format: Format,
+) -> Result<Vec<(String, TableChecksum)>> {
+    let ctx = SessionContext::new();
+    register_tables(&ctx, benchmark, data_path, format).await?;
+
+    let mut checksums = vec![];
+    for table in benchmark.get_table_names() {
+        info!(table, path = data_path, "Computing checksum");
+        checksums.push((
+            table.to_owned(),
+            table_checksum(&ctx, benchmark, table).await?,
+        ));
+    }
+    Ok(checksums)
+}
+
+/// Compare the row counts and checksums of every table in two data sets, which can be in
+/// different formats and have been generated with different numbers of partitions
+pub async fn compare_datasets(
+    benchmark: &dyn Tpc,
+    left_path: &str,
+    left_format: Format,
+    right_path: &str,
+    right_format: Format,
+) -> Result<Vec<TableComparison>> {
+    let left = table_checksums(benchmark, left_path, left_format).await?;
+    let right = table_checksums(benchmark, right_path, right_format).await?;
+    Ok(left
+        .into_iter()
+        .zip(right)
+        .map(|((table, left), (_, right))| TableComparison { table, left, right })
+        .collect())
+}
+
+async fn table_checksum(
+    ctx: &SessionContext,
+    benchmark: &dyn Tpc,
+    table: &str,
+) -> Result<TableChecksum> {
+    // select the columns of the schema by name so that the `ignore` column of the raw files
+    // and the column order of the files do not affect the checksum
+    let columns = benchmark
+        .get_schema(table)?
+        .fields()
+        .iter()
+        .map(|f| f.name().as_str())
+        .filter(|name| *name != "ignore")
+        .collect::<Vec<_>>()
+        .join(", ");
+    let df = ctx
+        .sql(&format!("SELECT {} FROM {}", columns, table))
+        .await?;
+
+    // hash the partitions concurrently, the sums can be combined in any order
+    let mut handles = vec![];
+    for mut stream in df.execute_stream_partitioned().await? {
+        handles.push(tokio::spawn(async move {
+            let mut total = TableChecksum {
+                rows: 0,
+                checksum: 0,
+            };
+            while let Some(batch) = stream.next().await {
+                let batch = batch?;
+                total.rows += batch.num_rows() as u64;
+                total.checksum = total.checksum.wrapping_add(batch_checksum(&batch)?);
+            }
+            Ok::<_, DataFusionError>(total)
+        }));
+    }
+    let mut total = TableChecksum {
+        rows: 0,
+        checksum: 0,
+    };
+    for handle in handles {
+        let partition = handle
+            .await
+            .map_err(|e| DataFusionError::Execution(e.to_string()))??;
+        total.rows += partition.rows;
+        total.checksum = total.checksum.wrapping_add(partition.checksum);
+    }
+    Ok(total)
+}
+
+/// Sum of the hashes of the rows in a batch. Each row is hashed in its text form, so a table
+/// read from the raw files and from Parquet has the same checksum.
+fn batch_checksum(batch: &RecordBatch) -> Result<u64> {
+    let options = FormatOptions::default();
+    let formatters = batch
+        .columns()
+        .iter()
+        .map(|c| ArrayFormatter::try_new(c.as_ref(), &options))
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+    let mut checksum = 0_u64;
+    let mut row_text = String::new();
+    for row in 0..batch.num_rows() {
+        row_text.clear();
+        for (column, formatter) in batch.columns().iter().zip(&formatters) {
+            if column.is_null(row) {
+                // distinguish nulls from empty strings
+                row_text.push('\0');
+            } else {
+                write!(row_text, "{}", formatter.value(row))
+                    .map_err(|e| DataFusionError::Execution(e.to_string()))?;
+            }
+            row_text.push('|');
+        }
+        checksum = checksum.wrapping_add(mix(fnv1a(row_text.as_bytes())));
+    }
+    Ok(checksum)
+}
+
+/// Spread the bits of a row hash before it is added to the sum, so that a change to any byte
+/// of a row affects all bits of the checksum
+fn mix(mut hash: u64) -> u64 {
+    hash ^= hash >> 33;
+    hash = hash.wrapping_mul(0xff51afd7ed558ccd);
+    hash ^= hash >> 33;
+    hash = hash.wrapping_mul(0xc4ceb9fe1a85ec53);
+    hash ^ (hash >> 33)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::progress::Progress;
+    use crate::tpch::TpcH;
+    use datafusion::arrow::array::{Int64Array, StringArray};
+    use datafusion::arrow::datatypes::{DataType, Field, Schema};
+    use std::sync::Arc;
+
+    fn batch(keys: Vec<i64>, names: Vec<Option<&str>>) -> RecordBatch {
+        let schema = Schema::new(vec![
+            Field::new("key", DataType::Int64, false),
+            Field::new("name", DataType::Utf8, true),
+        ]);
+        RecordBatch::try_new(
+            Arc::new(schema),
+            vec![
+                Arc::new(Int64Array::from(keys)),
+                Arc::new(StringArray::from(names)),
+            ],
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn checksum_does_not_depend_on_row_order() -> Result<()> {
+        let a = batch_checksum(&batch(vec![1, 2], vec![Some("a"), Some("b")]))?;
+        let b = batch_checksum(&batch(vec![2, 1], vec![Some("b"), Some("a")]))?;
+        assert_eq!(a, b);
+        let changed = batch_checksum(&batch(vec![1, 2], vec![Some("a"), Some("c")]))?;
+        assert_ne!(a, changed);
+        // a null is not the same as an empty string
+        let null = batch_checksum(&batch(vec![1], vec![None]))?;
+        let empty = batch_checksum(&batch(vec![1], vec![Some("")]))?;
+        assert_ne!(null, empty);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn raw_and_parquet_match() -> Result<()> {
+        let raw = tempfile::tempdir()?;
+        let parquet = tempfile::tempdir()?;
+        let raw_path = raw.path().to_str().unwrap();
+        let parquet_path = parquet.path().to_str().unwrap();
+        let tpch = TpcH::new();
+        tpch.generate_native(0.001, 1, raw_path, Some(7), &Progress::none())?;
+        tpch.generate_parquet(0.001, 2, parquet_path, Some(7), &Progress::none())?;
+
+        let comparisons =
+            compare_datasets(&tpch, raw_path, Format::Raw, parquet_path, Format::Parquet).await?;
+        assert_eq!(8, comparisons.len());
+        for comparison in &comparisons {
+            assert!(comparison.is_match(), "{:?}", comparison);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,455 @@
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::fs;
+use std::path::Path;
+
+use datafusion::arrow::datatypes::{DataType, Field, Schema};
+use datafusion::error::{DataFusionError, Result};
+
+use crate::profile::SchemaProfile;
+use crate::{Format, Tpc};
+
+/// Generate DDL statements for every table in the benchmark.
+///
+/// The dialect is `datafusion` for `CREATE EXTERNAL TABLE` statements that can be executed by
+/// `datafusion-cli` or Ballista, `spark` for `CREATE TABLE ... USING` statements, or `hive` for
+/// `CREATE EXTERNAL TABLE ... STORED AS` statements.
+///
+/// The data sets written by this crate split each table into `part-N` files and are never
+/// partitioned by column. When a table directory was rewritten by another tool into Hive-style
+/// `column=value` subdirectories the table is declared as partitioned by those columns. The column
+/// types are those of the schema profile the data was converted with.
+///
+/// DataFusion only lists `.csv` files for `STORED AS CSV` tables, so it cannot read the `.tbl`
+/// and `.dat` files of raw data sets and the `datafusion` dialect is only supported for Parquet.
+pub fn create_tables(
+    benchmark: &dyn Tpc,
+    data_path: &str,
+    format: Format,
+    dialect: &str,
+    profile: &SchemaProfile,
+) -> Result<String> {
+    let mut ddl = String::new();
+    for table in benchmark.get_table_names() {
+        let location = format!("{}/", format.table_path(benchmark, data_path, table));
+        let partition_cols = find_partition_columns(Path::new(&location))?;
+        let schema = profile.get_schema(benchmark, table)?;
+        let statement = match dialect {
+            "datafusion" => datafusion_table(table, &partition_cols, format, &location)?,
+            "spark" => spark_table(table, &schema, &partition_cols, format, &location)?,
+            "hive" => hive_table(table, &schema, &partition_cols, format, &location)?,
+            other => {
+                return Err(DataFusionError::NotImplemented(format!(
+                    "Invalid dialect: {}",
+                    other
+                )))
+            }
+        };
+        ddl.push_str(&statement);
+        ddl.push_str(";\n");
+    }
+    Ok(ddl)
+}
+
+fn datafusion_table(
+    table: &str,
+    partition_cols: &[String],
+    format: Format,
+    location: &str,
+) -> Result<String> {
+    if format != Format::Parquet {
+        return Err(DataFusionError::NotImplemented(
+            "The datafusion dialect only supports Parquet data sets, since DataFusion only reads \
+             files with a .csv extension for CSV tables. Convert the data set to Parquet first."
+                .to_owned(),
+        ));
+    }
+    let partitioned_by = if partition_cols.is_empty() {
+        "".to_owned()
+    } else {
+        format!(" PARTITIONED BY ({})", partition_cols.join(", "))
+    };
+    // the schema is read from the Parquet files
+    Ok(format!(
+        "CREATE EXTERNAL TABLE {} STORED AS PARQUET{} LOCATION '{}'",
+        table, partitioned_by, location
+    ))
+}
+
+fn spark_table(
+    table: &str,
+    schema: &Schema,
+    partition_cols: &[String],
+    format: Format,
+    location: &str,
+) -> Result<String> {
+    let using = if format == Format::Parquet {
+        "USING parquet".to_owned()
+    } else {
+        "USING csv OPTIONS (sep '|', header 'false')".to_owned()
+    };
+    let partitioned_by = if partition_cols.is_empty() {
+        "".to_owned()
+    } else {
+        format!("\nPARTITIONED BY ({})", partition_cols.join(", "))
+    };
+    Ok(format!(
+        "CREATE TABLE {} (\n{}\n) {}{}\nLOCATION '{}'",
+        table,
+        column_definitions(
+            &with_partition_columns(schema, partition_cols),
+            &[],
+            true,
+            hive_type
+        )?,
+        using,
+        partitioned_by,
+        location
+    ))
+}
+
+fn hive_table(
+    table: &str,
+    schema: &Schema,
+    partition_cols: &[String],
+    format: Format,
+    location: &str,
+) -> Result<String> {
+    let stored_as = if format == Format::Parquet {
+        "STORED AS PARQUET".to_owned()
+    } else {
+        "ROW FORMAT DELIMITED FIELDS TERMINATED BY '|'\nSTORED AS TEXTFILE".to_owned()
+    };
+    // Hive declares partition columns separately from the data columns
+    let partitioned_by = if partition_cols.is_empty() {
+        "".to_owned()
+    } else {
+        let mut columns = vec![];
+        for name in partition_cols {
+            let data_type = match schema.field_with_name(name) {
+                Ok(field) => hive_type(field.data_type())?,
+                Err(_) => "STRING".to_owned(),
+            };
+            columns.push(format!("{} {}", name, data_type));
+        }
+        format!("\nPARTITIONED BY ({})", columns.join(", "))
+    };
+    Ok(format!(
+        "CREATE EXTERNAL TABLE {} (\n{}\n){}\n{}\nLOCATION '{}'",
+        table,
+        column_definitions(schema, partition_cols, true, hive_type)?,
+        partitioned_by,
+        stored_as,
+        location
+    ))
+}
+
+/// Find the names of the Hive-style partition columns by following the first `column=value`
+/// subdirectory at each level of the table directory
+fn find_partition_columns(table_path: &Path) -> Result<Vec<String>> {
+    let mut columns = vec![];
+    let mut dir = table_path.to_path_buf();
+    while dir.is_dir() {
+        let mut next = None;
+        let mut entries = fs::read_dir(&dir)?
+            .map(|entry| entry.map(|e| e.path()))
+            .collect::<std::io::Result<Vec<_>>>()?;
+        entries.sort();
+        for path in entries {
+            let name = path
+                .file_name()
+                .and_then(|n| n.to_str())
+                .unwrap_or_default()
+                .to_owned();
+            if path.is_dir() {
+                if let Some((column, _)) = name.split_once('=') {
+                    columns.push(column.to_owned());
+                    next = Some(path);
+                    break;
+                }
+            }
+        }
+        match next {
+            Some(path) => dir = path,
+            None => break,
+        }
+    }
+    Ok(columns)
+}
+
+/// Add any partition columns that are not part of the benchmark schema as strings, for dialects
+/// that declare partition columns in the column list
+fn with_partition_columns(schema: &Schema, partition_cols: &[String]) -> Schema {
+    let mut fields: Vec<Field> = schema.fields().iter().map(|f| f.as_ref().clone()).collect();
+    for name in partition_cols {
+        if schema.field_with_name(name).is_err() {
+            fields.push(Field::new(name, DataType::Utf8, true));
+        }
+    }
+    Schema::new(fields)
+}
+
+fn column_definitions(
+    schema: &Schema,
+    exclude: &[String],
+    skip_not_null: bool,
+    sql_type: fn(&DataType) -> Result<String>,
+) -> Result<String> {
+    let mut columns = vec![];
+    for field in schema.fields() {
+        if exclude.contains(field.name()) {
+            continue;
+        }
+        let not_null = !skip_not_null && !field.is_nullable();
+        columns.push(format!(
+            "  {} {}{}",
+            field.name(),
+            sql_type(field.data_type())?,
+            if not_null { " NOT NULL" } else { "" }
+        ));
+    }
+    Ok(columns.join(",\n"))
+}
+
+/// Spark and Hive share the same names for the types used by the benchmark schemas
+fn hive_type(data_type: &DataType) -> Result<String> {
+    Ok(match data_type {
+        DataType::Int32 => "INT".to_owned(),
+        DataType::Int64 => "BIGINT".to_owned(),
+        DataType::Float64 => "DOUBLE".to_owned(),
+        DataType::Utf8 => "STRING".to_owned(),
+        DataType::Date32 => "DATE".to_owned(),
+        DataType::Decimal128(p, s) => format!("DECIMAL({},{})", p, s),
+        other => {
+            return Err(DataFusionError::NotImplemented(format!(
+                "Unsupported data type: {}",
+                other
+            )))
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::progress::Progress;
+    use crate::tpch::TpcH;
+    use datafusion::arrow::array::{Array, Int64Array};
+    use datafusion::prelude::SessionContext;
+
+    #[tokio::test]
+    async fn datafusion_ddl_reads_parquet_tables() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let data_path = dir.path().to_str().unwrap();
+        let tpch = TpcH::new();
+        tpch.generate_parquet(0.001, 2, data_path, None, &Progress::none())?;
+
+        let ddl = create_tables(
+            &tpch,
+            data_path,
+            Format::Parquet,
+            "datafusion",
+            &SchemaProfile::default(),
+        )?;
+        // the statements are understood by a context without any extensions, like datafusion-cli
+        let ctx = SessionContext::new();
+        for statement in ddl.split(";\n").filter(|s| !s.is_empty()) {
+            ctx.sql(statement).await?;
+        }
+        let batches = ctx
+            .sql("SELECT COUNT(*) FROM nation")
+            .await?
+            .collect()
+            .await?;
+        let count = batches[0]
+            .column(0)
+            .as_any()
+            .downcast_ref::<Int64Array>()
+            .unwrap()
+            .value(0);
+        assert_eq!(25, count);
+        Ok(())
+    }
+
+    #[test]
+    fn datafusion_ddl_rejects_raw_data() {
+        let err = create_tables(
+            &TpcH::new(),
+            "/tmp/tpch",
+            Format::Raw,
+            "datafusion",
+            &SchemaProfile::default(),
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("only supports Parquet"), "{}", err);
+    }
+
+    /// Create empty table directories, with Hive-style partition directories for some tables
+    fn data_set(format: Format, partitions: &[(&str, &str)]) -> Result<tempfile::TempDir> {
+        let tpch = TpcH::new();
+        let dir = tempfile::tempdir()?;
+        let data_path = dir.path().to_str().unwrap();
+        for table in tpch.get_table_names() {
+            fs::create_dir(format.table_path(&tpch, data_path, table))?;
+        }
+        for (table, partition) in partitions {
+            let table_path = format.table_path(&tpch, data_path, table);
+            fs::create_dir_all(format!("{}/{}", table_path, partition))?;
+        }
+        Ok(dir)
+    }
+
+    /// Find the statement for a table and replace the data path with `<data>`
+    fn statement(ddl: &str, table: &str, dir: &tempfile::TempDir) -> String {
+        ddl.split(";\n")
+            .find(|s| s.lines().next().unwrap().split(' ').any(|w| w == table))
+            .unwrap()
+            .replace(dir.path().to_str().unwrap(), "<data>")
+    }
+
+    fn create(dir: &tempfile::TempDir, format: Format, dialect: &str) -> Result<String> {
+        create_tables(
+            &TpcH::new(),
+            dir.path().to_str().unwrap(),
+            format,
+            dialect,
+            &SchemaProfile::default(),
+        )
+    }
+
+    #[test]
+    fn spark_ddl() -> Result<()> {
+        let dir = data_set(Format::Parquet, &[])?;
+        let ddl = create(&dir, Format::Parquet, "spark")?;
+        assert_eq!(
+            "CREATE TABLE region (\n  r_regionkey BIGINT,\n  r_name STRING,\n  \
+             r_comment STRING,\n  ignore STRING\n) USING parquet\nLOCATION '<data>/region.parquet/'",
+            statement(&ddl, "region", &dir)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn spark_ddl_for_raw_data() -> Result<()> {
+        let dir = data_set(Format::Raw, &[])?;
+        let ddl = create(&dir, Format::Raw, "spark")?;
+        assert_eq!(
+            "CREATE TABLE region (\n  r_regionkey BIGINT,\n  r_name STRING,\n  \
+             r_comment STRING,\n  ignore STRING\n) USING csv OPTIONS (sep '|', header 'false')\n\
+             LOCATION '<data>/region.tbl/'",
+            statement(&ddl, "region", &dir)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn hive_ddl() -> Result<()> {
+        let dir = data_set(Format::Parquet, &[])?;
+        let ddl = create(&dir, Format::Parquet, "hive")?;
+        assert_eq!(
+            "CREATE EXTERNAL TABLE region (\n  r_regionkey BIGINT,\n  r_name STRING,\n  \
+             r_comment STRING,\n  ignore STRING\n)\nSTORED AS PARQUET\nLOCATION '<data>/region.parquet/'",
+            statement(&ddl, "region", &dir)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn hive_ddl_for_raw_data() -> Result<()> {
+        let dir = data_set(Format::Raw, &[])?;
+        let ddl = create(&dir, Format::Raw, "hive")?;
+        assert_eq!(
+            "CREATE EXTERNAL TABLE region (\n  r_regionkey BIGINT,\n  r_name STRING,\n  \
+             r_comment STRING,\n  ignore STRING\n)\nROW FORMAT DELIMITED FIELDS TERMINATED BY '|'\n\
+             STORED AS TEXTFILE\nLOCATION '<data>/region.tbl/'",
+            statement(&ddl, "region", &dir)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn datafusion_ddl() -> Result<()> {
+        let dir = data_set(Format::Parquet, &[])?;
+        let ddl = create(&dir, Format::Parquet, "datafusion")?;
+        assert_eq!(
+            "CREATE EXTERNAL TABLE region STORED AS PARQUET LOCATION '<data>/region.parquet/'",
+            statement(&ddl, "region", &dir)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn generated_data_is_not_partitioned() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let data_path = dir.path().to_str().unwrap();
+        TpcH::new().generate_native(0.001, 2, data_path, None, &Progress::none())?;
+        for dialect in ["spark", "hive"] {
+            let ddl = create(&dir, Format::Raw, dialect)?;
+            assert!(!ddl.contains("PARTITIONED BY"), "{}", ddl);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn partition_columns_are_detected() -> Result<()> {
+        let partitions = [("nation", "n_regionkey=0"), ("orders", "year=1995/month=1")];
+        let dir = data_set(Format::Parquet, &partitions)?;
+
+        let ddl = create(&dir, Format::Parquet, "datafusion")?;
+        assert_eq!(
+            "CREATE EXTERNAL TABLE nation STORED AS PARQUET PARTITIONED BY (n_regionkey) \
+             LOCATION '<data>/nation.parquet/'",
+            statement(&ddl, "nation", &dir)
+        );
+        assert_eq!(
+            "CREATE EXTERNAL TABLE orders STORED AS PARQUET PARTITIONED BY (year, month) \
+             LOCATION '<data>/orders.parquet/'",
+            statement(&ddl, "orders", &dir)
+        );
+
+        // Spark declares partition columns in the column list, as strings if they are not part
+        // of the schema
+        let ddl = create(&dir, Format::Parquet, "spark")?;
+        assert_eq!(
+            "CREATE TABLE nation (\n  n_nationkey BIGINT,\n  n_name STRING,\n  \
+             n_regionkey BIGINT,\n  n_comment STRING,\n  ignore STRING\n) USING parquet\n\
+             PARTITIONED BY (n_regionkey)\nLOCATION '<data>/nation.parquet/'",
+            statement(&ddl, "nation", &dir)
+        );
+        let orders = statement(&ddl, "orders", &dir);
+        assert!(
+            orders.contains(
+                "  year STRING,\n  month STRING\n) USING parquet\n\
+                 PARTITIONED BY (year, month)\n"
+            ),
+            "{}",
+            orders
+        );
+
+        // Hive declares partition columns separately from the data columns
+        let ddl = create(&dir, Format::Parquet, "hive")?;
+        assert_eq!(
+            "CREATE EXTERNAL TABLE nation (\n  n_nationkey BIGINT,\n  n_name STRING,\n  \
+             n_comment STRING,\n  ignore STRING\n)\nPARTITIONED BY (n_regionkey BIGINT)\nSTORED AS PARQUET\n\
+             LOCATION '<data>/nation.parquet/'",
+            statement(&ddl, "nation", &dir)
+        );
+        let orders = statement(&ddl, "orders", &dir);
+        assert!(
+            orders.contains(")\nPARTITIONED BY (year STRING, month STRING)\n"),
+            "{}",
+            orders
+        );
+        Ok(())
+    }
+}
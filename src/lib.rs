@@ -10,67 +10,272 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use std::fs;
+use std::fmt;
+use std::fs::{self, File, OpenOptions};
 use std::io::Result;
 use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::str::FromStr;
+use std::thread;
 use std::time::Instant;
 
 use async_trait::async_trait;
-use datafusion::arrow::datatypes::Schema;
+use datafusion::arrow::datatypes::{DataType, Field, Schema};
 use datafusion::error::DataFusionError;
 use datafusion::parquet::basic::Compression;
 use datafusion::parquet::file::properties::WriterProperties;
 use datafusion::prelude::*;
+use profile::SchemaProfile;
+use progress::{Progress, ProgressEvent};
+use tracing::{debug, info, warn};
 
+pub mod check;
+pub mod compare;
+pub mod ddl;
+pub mod logging;
+pub mod manifest;
+pub mod metric;
+pub mod profile;
+pub mod progress;
+pub mod recipe;
+pub mod report;
+pub mod runner;
+pub mod setup;
+pub mod ssb;
 pub mod tpcds;
 pub mod tpch;
+pub mod validate;
+pub mod verify;
 
 #[async_trait]
 pub trait Tpc {
+    /// Run the data generator in `input_path` to generate `partitions` partitions in parallel.
+    /// With a `seed` the generator produces a different data set with the same distributions,
+    /// which returns an error for generators that cannot be seeded. The finished generator
+    /// processes and the size of the output files are reported to `progress`.
     fn generate(
         &self,
-        scale: usize,
+        scale: f64,
         partitions: usize,
         input_path: &str,
         output_path: &str,
+        seed: Option<u64>,
+        progress: &Progress,
     ) -> Result<()>;
 
     fn get_table_names(&self) -> Vec<&str>;
 
     fn get_table_ext(&self) -> &str;
 
-    fn get_schema(&self, table: &str) -> Schema;
+    /// Schema of a table, or an error if the table is not part of the benchmark
+    fn get_schema(&self, table: &str) -> Result<Schema>;
+
+    /// Columns of the primary key of a table
+    fn get_primary_key(&self, table: &str) -> Result<Vec<&str>>;
+
+    /// Foreign keys from a table to the primary keys of other tables
+    fn get_foreign_keys(&self, table: &str) -> Result<Vec<ForeignKey>>;
+
+    /// Whether a table is a fact or dimension table, or an error if the table is not part of the
+    /// benchmark
+    fn get_table_type(&self, table: &str) -> Result<TableType>;
+
+    /// Number of rows the specification defines for a table at a scale factor, or `None` if the
+    /// number is not known for that scale factor
+    fn get_row_count(&self, table: &str, scale: f64) -> Option<u64>;
+}
+
+/// Error for a table that is not part of a benchmark
+pub(crate) fn unknown_table(benchmark: &str, table: &str) -> std::io::Error {
+    std::io::Error::new(
+        std::io::ErrorKind::InvalidInput,
+        format!("Unknown {} table '{}'", benchmark, table),
+    )
+}
+
+/// Whether a table records events, such as sales, or describes the entities they refer to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TableType {
+    Fact,
+    Dimension,
+}
+
+/// A foreign key from columns of one table to the primary key of another table
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ForeignKey {
+    pub columns: Vec<String>,
+    pub referenced_table: String,
+    pub referenced_columns: Vec<String>,
+}
+
+impl ForeignKey {
+    pub fn new(columns: &[&str], referenced_table: &str, referenced_columns: &[&str]) -> Self {
+        Self {
+            columns: columns.iter().map(|c| c.to_string()).collect(),
+            referenced_table: referenced_table.to_owned(),
+            referenced_columns: referenced_columns.iter().map(|c| c.to_string()).collect(),
+        }
+    }
+}
+
+/// Format of a data set on disk
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    /// The output of `convert_to_parquet`, with one `<table>.parquet` directory per table
+    Parquet,
+    /// The delimited files produced by `generate`, with one `<table>.<ext>` directory per table
+    Raw,
+}
+
+impl Format {
+    /// Path of the directory (or file) containing the data for a table
+    pub fn table_path(&self, benchmark: &dyn Tpc, data_path: &str, table: &str) -> String {
+        match self {
+            Format::Parquet => format!("{}/{}.parquet", data_path, table),
+            Format::Raw => format!("{}/{}.{}", data_path, table, benchmark.get_table_ext()),
+        }
+    }
+}
+
+impl FromStr for Format {
+    type Err = DataFusionError;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "parquet" => Ok(Format::Parquet),
+            "raw" => Ok(Format::Raw),
+            other => Err(DataFusionError::NotImplemented(format!(
+                "Invalid input format: {}",
+                other
+            ))),
+        }
+    }
 }
 
+impl fmt::Display for Format {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Format::Parquet => write!(f, "parquet"),
+            Format::Raw => write!(f, "raw"),
+        }
+    }
+}
+
+/// Register every table of a data set produced by `generate` or `convert_to_parquet` in the
+/// context. Each table can either be a directory of files, as produced by this crate, or a single
+/// file such as `lineitem.tbl` when the generator was run by hand.
+pub async fn register_tables(
+    ctx: &SessionContext,
+    benchmark: &dyn Tpc,
+    data_path: &str,
+    format: Format,
+) -> datafusion::error::Result<()> {
+    for table in benchmark.get_table_names() {
+        let path = format.table_path(benchmark, data_path, table);
+        let path = match fs::metadata(&path) {
+            // directory URLs must end with a separator to be listed
+            Ok(metadata) if metadata.is_dir() => format!("{}/", path),
+            Ok(_) => path,
+            Err(e) => {
+                return Err(DataFusionError::Execution(format!(
+                    "Data for table {} not found at {}: {}",
+                    table, path, e
+                )))
+            }
+        };
+        let single_file = !path.ends_with('/');
+        match format {
+            Format::Parquet => {
+                let options = ParquetReadOptions {
+                    file_extension: if single_file { "" } else { ".parquet" },
+                    ..Default::default()
+                };
+                ctx.register_parquet(table, &path, options).await?;
+            }
+            Format::Raw => {
+                let schema = with_ignore_column(benchmark.get_schema(table)?);
+                // the raw directories can contain a mix of .tbl and .dat files so we do not
+                // filter on file extension here
+                let options = CsvReadOptions::new()
+                    .schema(&schema)
+                    .delimiter(b'|')
+                    .has_header(false)
+                    .file_extension("");
+                ctx.register_csv(table, &path, options).await?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Every row of the raw files ends with a delimiter, which the TPC-H schemas declare as an
+/// `ignore` column. Add the same column to schemas that do not have it.
+pub(crate) fn with_ignore_column(schema: Schema) -> Schema {
+    if schema.field_with_name("ignore").is_ok() {
+        return schema;
+    }
+    let mut fields: Vec<Field> = schema.fields().iter().map(|f| f.as_ref().clone()).collect();
+    fields.push(Field::new("ignore", DataType::Utf8, true));
+    Schema::new(fields)
+}
+
+/// Convert the raw files of every table to Parquet, with the column types of the schema
+/// profile. Each converted file and the number of rows converted are reported to `progress`.
 pub async fn convert_to_parquet(
     benchmark: &dyn Tpc,
     input_path: &str,
     output_path: &str,
+    profile: &SchemaProfile,
+    compression: &str,
+    progress: &Progress,
 ) -> datafusion::error::Result<()> {
+    // the size of the input files is used to estimate the time remaining
+    let mut files = 0;
+    let mut input_bytes = 0;
     for table in benchmark.get_table_names() {
-        println!("Converting table {}", table);
-        let schema = benchmark.get_schema(table);
+        let path = format!("{}/{}.{}", input_path, table, benchmark.get_table_ext());
+        if Path::new(&path).is_dir() {
+            for file in fs::read_dir(path)? {
+                files += 1;
+                input_bytes += file?.metadata()?.len();
+            }
+        }
+    }
+    progress.report(ProgressEvent::ConversionStarted { files, input_bytes });
+
+    for table in benchmark.get_table_names() {
+        info!(table, "Converting table");
+        // every row ends with a delimiter, which is read into a trailing `ignore` column
+        let schema = with_ignore_column(profile.get_schema(benchmark, table)?);
 
         let file_ext = format!(".{}", benchmark.get_table_ext());
         let options = CsvReadOptions::new()
             .schema(&schema)
+            .has_header(false)
             .delimiter(b'|')
             .file_extension(&file_ext);
 
         let path = format!("{}/{}.{}", input_path, table, benchmark.get_table_ext());
         let path = Path::new(&path);
         if !path.exists() {
-            panic!("path does not exist: {:?}", path);
+            return Err(DataFusionError::Execution(format!(
+                "Data for table {} not found at {}",
+                table,
+                path.display()
+            )));
         }
 
         // create output dir
         let output_dir_name = format!("{}/{}.parquet", output_path, table);
         let output_dir = Path::new(&output_dir_name);
         if output_dir.exists() {
-            panic!("output dir already exists: {}", output_dir.display());
+            return Err(DataFusionError::Execution(format!(
+                "Output directory already exists: {}",
+                output_dir.display()
+            )));
         }
-        println!("Creating directory: {}", output_dir.display());
-        fs::create_dir(&output_dir)?;
+        debug!(path = %output_dir.display(), "Creating directory");
+        fs::create_dir(output_dir)?;
 
         let x = PathBuf::from(path);
         let mut file_vec = vec![];
@@ -83,13 +288,16 @@ pub async fn convert_to_parquet(
         }
 
         let mut part = 0;
+        // the rows of each Parquet file are counted once, as it is moved into the table
+        let mut table_rows = 0;
         for file in &file_vec {
+            let file_size = file.metadata()?.len();
             let stub = file.file_name().to_str().unwrap().to_owned();
             let stub = &stub[0..stub.len() - 4]; // remove .dat or .tbl
                                                  // write to temp dir that will contain nested dirs
                                                  // example: /tmp/nation-temp.parquet/part-1.parquet/part-0.parquet
             let output_parts_dir = format!("{}/{}-temp.parquet", output_dir.display(), stub);
-            println!("Writing {}", output_parts_dir);
+            debug!(path = %output_parts_dir, "Writing");
             let options = options.clone();
             // async move {
             convert_tbl(
@@ -97,47 +305,140 @@ pub async fn convert_to_parquet(
                 &output_parts_dir,
                 &options,
                 "parquet",
-                "snappy",
+                compression,
                 8192,
             )
             .await?;
             // }
 
+            let mut output_bytes = 0;
             let paths = fs::read_dir(&output_parts_dir)?;
             for path in paths {
                 let path = path?;
+                output_bytes += path.metadata()?.len();
                 let dest_file = format!("{}/part-{}.parquet", output_dir.display(), part);
                 part += 1;
                 let dest_path = Path::new(&dest_file);
-                move_or_copy(&path.path(), &dest_path)?;
+                move_or_copy(&path.path(), dest_path)?;
+                table_rows += verify::count_rows(dest_path, Format::Parquet)?;
             }
-            println!("Removing {}", output_parts_dir);
+            debug!(path = %output_parts_dir, "Removing");
             fs::remove_dir_all(Path::new(&output_parts_dir))?;
+
+            progress.report(ProgressEvent::FileConverted {
+                table: table.to_owned(),
+                input: file.path().display().to_string(),
+                input_bytes: file_size,
+                output_bytes,
+                rows: table_rows,
+            });
         }
     }
 
+    progress.report(ProgressEvent::Finished);
     Ok(())
 }
 
+/// Check that a scale factor is positive, which also rejects NaN. Fractional scale factors are
+/// supported by all generators.
+pub fn check_scale(scale: f64) -> Result<()> {
+    if scale.is_nan() || scale <= 0.0 {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            format!("Scale factor must be positive: {}", scale),
+        ));
+    }
+    Ok(())
+}
+
+/// Create a temporary working directory for one run of a generator inside the output path, so
+/// that runs do not share the files they write and the generated files can be moved rather than
+/// copied. The directory and anything left in it are removed when it is dropped.
+pub(crate) fn generator_work_dir(output_path: &str) -> Result<tempfile::TempDir> {
+    tempfile::Builder::new()
+        .prefix(".generate-")
+        .tempdir_in(output_path)
+}
+
+/// Command to run a dbgen-style generator from `generator_path` in `work_dir`. dbgen writes
+/// the tables to `DSS_PATH` and reads `dists.dss` from `DSS_CONFIG`, so nothing is written to
+/// the generator directory.
+pub(crate) fn dbgen_command(generator_path: &str, work_dir: &Path) -> Result<Command> {
+    // the working directory changes, so relative paths no longer refer to the generator
+    let generator_path = fs::canonicalize(generator_path)?;
+    let mut command = Command::new(generator_path.join("dbgen"));
+    command
+        .current_dir(work_dir)
+        .env("DSS_PATH", work_dir)
+        .env("DSS_CONFIG", &generator_path);
+    Ok(command)
+}
+
+/// Open the log file for the output of a generator process, `<output_path>/logs/<name>.log`,
+/// appending to it if it exists
+pub(crate) fn child_log(output_path: &str, name: &str) -> Result<(File, PathBuf)> {
+    let log_dir = Path::new(output_path).join("logs");
+    fs::create_dir_all(&log_dir)?;
+    let log_path = log_dir.join(format!("{}.log", name));
+    let log = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&log_path)?;
+    Ok((log, log_path))
+}
+
+/// Run a generator process with its stdout and stderr written to its log file, see
+/// `child_log`. A generator that does not exit successfully is an error that refers to the log.
+pub(crate) fn run_logged(command: &mut Command, output_path: &str, name: &str) -> Result<()> {
+    let (log, log_path) = child_log(output_path, name)?;
+    debug!(command = ?command, log = %log_path.display(), "Running generator");
+    let status = command.stdout(log.try_clone()?).stderr(log).status()?;
+    if !status.success() {
+        warn!(%status, log = %log_path.display(), "Generator failed");
+        return Err(std::io::Error::other(format!(
+            "{} failed with {}, see {}",
+            name,
+            status,
+            log_path.display()
+        )));
+    }
+    debug!(%status, log = %log_path.display(), "Generator finished");
+    Ok(())
+}
+
+/// Wait for the threads that run the generators, returning the first error
+pub(crate) fn join_generators(handles: Vec<thread::JoinHandle<Result<()>>>) -> Result<()> {
+    let mut result = Ok(());
+    for handle in handles {
+        let status = handle
+            .join()
+            .unwrap_or_else(|_| Err(std::io::Error::other("Generator thread panicked")));
+        if result.is_ok() {
+            result = status;
+        }
+    }
+    result
+}
+
 pub(crate) fn move_or_copy(
     source_path: &Path,
     dest_path: &Path,
 ) -> std::result::Result<(), std::io::Error> {
-    if is_same_device(&source_path, &dest_path)? {
-        println!(
-            "Moving {} to {}",
-            source_path.display(),
-            dest_path.display()
+    if is_same_device(source_path, dest_path)? {
+        debug!(
+            source = %source_path.display(),
+            dest = %dest_path.display(),
+            "Moving file"
         );
-        fs::rename(&source_path, &dest_path)
+        fs::rename(source_path, dest_path)
     } else {
-        println!(
-            "Copying {} to {}",
-            source_path.display(),
-            dest_path.display()
+        debug!(
+            source = %source_path.display(),
+            dest = %dest_path.display(),
+            "Copying file"
         );
-        fs::copy(&source_path, &dest_path)?;
-        fs::remove_file(&source_path)
+        fs::copy(source_path, dest_path)?;
+        fs::remove_file(source_path)
     }
 }
 
@@ -165,10 +466,10 @@ pub async fn convert_tbl(
     compression: &str,
     batch_size: usize,
 ) -> datafusion::error::Result<()> {
-    println!(
-        "Converting '{}' to {}",
-        input_path.display(),
-        output_filename
+    info!(
+        input = %input_path.display(),
+        output = output_filename,
+        "Converting file"
     );
 
     let start = Instant::now();
@@ -181,7 +482,7 @@ pub async fn convert_tbl(
     let df = ctx.read_csv(&csv_filename, options.clone()).await?;
 
     match file_format {
-        "csv" => df.write_csv(&output_filename).await?,
+        "csv" => df.write_csv(output_filename).await?,
         "parquet" => {
             let compression = match compression {
                 "none" => Compression::UNCOMPRESSED,
@@ -202,7 +503,7 @@ pub async fn convert_tbl(
                 .set_compression(compression)
                 .build();
 
-            df.write_parquet(&output_filename, Some(props)).await?
+            df.write_parquet(output_filename, Some(props)).await?
         }
         other => {
             return Err(DataFusionError::NotImplemented(format!(
@@ -211,7 +512,86 @@ pub async fn convert_tbl(
             )))
         }
     }
-    println!("Conversion completed in {} ms", start.elapsed().as_millis());
+    info!(elapsed = ?start.elapsed(), "Conversion completed");
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tpch::TpcH;
+
+    #[tokio::test]
+    async fn convert_reports_cumulative_rows() -> datafusion::error::Result<()> {
+        let benchmark = TpcH::new();
+        let input = tempfile::tempdir()?;
+        let output = tempfile::tempdir()?;
+        let input_path = input.path().to_str().unwrap();
+        for table in benchmark.get_table_names() {
+            fs::create_dir(Format::Raw.table_path(&benchmark, input_path, table))?;
+        }
+        let nation = include_str!("tpch/testdata/nation.tbl");
+        let nation_path = Format::Raw.table_path(&benchmark, input_path, "nation");
+        fs::write(format!("{}/part-0.tbl", nation_path), nation)?;
+        fs::write(format!("{}/part-1.tbl", nation_path), nation)?;
+
+        let (progress, events) = Progress::channel();
+        convert_to_parquet(
+            &benchmark,
+            input_path,
+            output.path().to_str().unwrap(),
+            &SchemaProfile::default(),
+            "snappy",
+            &progress,
+        )
+        .await?;
+
+        let rows: Vec<u64> = events
+            .try_iter()
+            .filter_map(|(event, _)| match event {
+                ProgressEvent::FileConverted { rows, .. } => Some(rows),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(vec![25, 50], rows);
+        let output_path =
+            Format::Parquet.table_path(&benchmark, output.path().to_str().unwrap(), "nation");
+        assert_eq!(
+            50,
+            verify::count_rows(Path::new(&output_path), Format::Parquet)?
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn convert_tpcds_with_ignore_column() -> datafusion::error::Result<()> {
+        let benchmark = tpcds::TpcDs::new();
+        let input = tempfile::tempdir()?;
+        let output = tempfile::tempdir()?;
+        let input_path = input.path().to_str().unwrap();
+        let output_path = output.path().to_str().unwrap();
+        for table in benchmark.get_table_names() {
+            fs::create_dir(Format::Raw.table_path(&benchmark, input_path, table))?;
+        }
+        let income_band = Format::Raw.table_path(&benchmark, input_path, "income_band");
+        fs::write(format!("{}/part-0.dat", income_band), "1|0|10000|\n")?;
+
+        convert_to_parquet(
+            &benchmark,
+            input_path,
+            output_path,
+            &SchemaProfile::default(),
+            "snappy",
+            &Progress::none(),
+        )
+        .await?;
+
+        let ctx = SessionContext::new();
+        register_tables(&ctx, &benchmark, output_path, Format::Parquet).await?;
+        let df = ctx.table("income_band").await?;
+        assert!(df.schema().field_with_unqualified_name("ignore").is_ok());
+        assert_eq!(1, df.count().await?);
+        Ok(())
+    }
+}
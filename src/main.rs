@@ -10,41 +10,82 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use std::io::Result;
-use std::path::PathBuf;
+use std::fs;
+use std::io::{Error, ErrorKind, Result};
+use std::path::{Path, PathBuf};
+use std::time::Instant;
 
+use chrono::Utc;
+use datafusion::error::DataFusionError;
 use structopt::StructOpt;
+use tracing::{error, info, info_span, Instrument, Level};
 
+use tpctools::check::check_constraints;
+use tpctools::compare::compare_datasets;
+use tpctools::ddl::create_tables;
+use tpctools::logging::{self, LogFormat};
+use tpctools::manifest::{GeneratorInfo, Manifest};
+use tpctools::metric::{min_streams, run_performance_test, write_report, QueryGenerator};
+use tpctools::profile::SchemaProfile;
+use tpctools::progress::Progress;
+use tpctools::recipe::Recipe;
+use tpctools::report::TimingRecorder;
+use tpctools::runner::{run_queries, write_summary};
+use tpctools::setup::{default_cache_path, setup_generator};
+use tpctools::ssb::Ssb;
 use tpctools::tpcds::TpcDs;
 use tpctools::tpch::TpcH;
-use tpctools::{convert_to_parquet, Tpc};
+use tpctools::verify::verify_row_counts;
+use tpctools::{check_scale, convert_to_parquet, Format, Tpc};
 
 #[derive(Debug, StructOpt)]
 struct GenerateOpt {
-    /// TPC benchmark to use (tpcds or tpch)
+    /// Benchmark to use (tpcds, tpch, jcch or ssb)
     #[structopt(short, long)]
     benchmark: String,
 
-    /// Scale factor
+    /// Scale factor, which can be fractional (such as 0.01) to generate small data sets
     #[structopt(short, long)]
-    scale: usize,
+    scale: f64,
 
     /// Number of partitions to generate in parallel
     #[structopt(short, long)]
     partitions: usize,
 
-    /// Path to tpcds-kit
+    /// Path to tpcds-kit, tpch-dbgen, the JCC-H dbgen or ssb-dbgen. TPC-H data is generated
+    /// natively when this is omitted.
     #[structopt(short, long, parse(from_os_str))]
-    generator_path: PathBuf,
+    generator_path: Option<PathBuf>,
 
     /// Output path
     #[structopt(short, long, parse(from_os_str))]
     output: PathBuf,
+
+    /// Output format (raw or parquet). Parquet is written directly without intermediate files,
+    /// which requires the native generator for TPC-H or streams the output of dsdgen for TPC-DS.
+    #[structopt(short, long, default_value = "raw")]
+    format: Format,
+
+    /// Number of TPC-H update sets to generate for the refresh functions, written to
+    /// <output>/refresh
+    #[structopt(long, default_value = "0")]
+    refresh_sets: usize,
+
+    /// Seed for the random number generators, to generate a different data set with the same
+    /// distributions. This is passed to dsdgen as -RNGSEED and is supported by the native TPC-H
+    /// generator, but not by dbgen.
+    #[structopt(long)]
+    seed: Option<u64>,
+
+    /// Path of a JSON report to write with the time taken by each partition, the size and row
+    /// count of each table and the peak memory use
+    #[structopt(parse(from_os_str), long)]
+    report: Option<PathBuf>,
 }
 
 #[derive(Debug, StructOpt)]
 struct ConvertOpt {
-    /// TPC benchmark to use (tpcds or tpch)
+    /// Benchmark to use (tpcds, tpch, jcch or ssb)
     #[structopt(short, long)]
     benchmark: String,
 
@@ -55,6 +96,226 @@ struct ConvertOpt {
     /// Output path
     #[structopt(parse(from_os_str), required = true, short = "o", long = "output")]
     output_path: PathBuf,
+
+    /// Schema profile for the column types of the Parquet files, as a comma-separated list of
+    /// default, int32-keys, int64-keys, float-decimals and string-dates
+    #[structopt(long, default_value = "default")]
+    schema: SchemaProfile,
+
+    /// Compression codec of the Parquet files (snappy, lz4 or none)
+    #[structopt(long, default_value = "snappy")]
+    compression: String,
+
+    /// Path of a JSON report to write with the time taken to convert each file, the size and
+    /// row count of each table, the compression ratio and the peak memory use
+    #[structopt(parse(from_os_str), long)]
+    report: Option<PathBuf>,
+}
+
+#[derive(Debug, StructOpt)]
+struct RunOpt {
+    /// Benchmark to use (tpcds, tpch, jcch or ssb)
+    #[structopt(short, long)]
+    benchmark: String,
+
+    /// Path to the generated or converted data
+    #[structopt(parse(from_os_str), required = true, short = "i", long = "input")]
+    input_path: PathBuf,
+
+    /// Format of the data (parquet or raw). Defaults to the format recorded in the manifest of
+    /// the data set, or parquet if there is no manifest.
+    #[structopt(short, long)]
+    format: Option<Format>,
+
+    /// Path to a directory containing one .sql file per query
+    #[structopt(parse(from_os_str), required = true, short = "q", long = "query-path")]
+    query_path: PathBuf,
+
+    /// Number of times to run each query
+    #[structopt(short = "n", long, default_value = "3")]
+    iterations: usize,
+
+    /// Directory containing reference answers (<query>.out) to validate the results against
+    #[structopt(parse(from_os_str), long = "answers")]
+    answers_path: Option<PathBuf>,
+
+    /// Directory to write the JSON summary to
+    #[structopt(parse(from_os_str), short = "o", long = "output")]
+    output_path: Option<PathBuf>,
+}
+
+#[derive(Debug, StructOpt)]
+struct QphhOpt {
+    /// Path to the converted TPC-H Parquet data, which is modified by the refresh functions
+    #[structopt(parse(from_os_str), required = true, short = "i", long = "input")]
+    input_path: PathBuf,
+
+    /// Path to the update sets generated with --refresh-sets
+    #[structopt(parse(from_os_str), required = true, short = "r", long = "refresh")]
+    refresh_path: PathBuf,
+
+    /// Path to a directory containing the queries as q1.sql to q22.sql, or the queries of each
+    /// stream in subdirectories 0 to S. With --qgen-path, the qgen templates 1.sql to 22.sql.
+    #[structopt(parse(from_os_str), required = true, short = "q", long = "query-path")]
+    query_path: PathBuf,
+
+    /// Path to a directory containing qgen and dists.dss, to generate the queries of each stream
+    /// with their own substitution parameters
+    #[structopt(parse(from_os_str), long = "qgen-path")]
+    qgen_path: Option<PathBuf>,
+
+    /// Seed of the qgen substitution parameters, which defaults to the current time as
+    /// mmddhhmmss like the specification's end of load timestamp
+    #[structopt(long)]
+    seed: Option<u64>,
+
+    /// Scale factor of the data
+    #[structopt(short, long)]
+    scale: f64,
+
+    /// Number of query streams in the throughput test (defaults to the minimum required by the
+    /// specification for the scale factor)
+    #[structopt(long)]
+    streams: Option<usize>,
+
+    /// Path of the JSON report to write
+    #[structopt(parse(from_os_str), short = "o", long = "output")]
+    output_path: Option<PathBuf>,
+}
+
+#[derive(Debug, StructOpt)]
+struct DdlOpt {
+    /// Benchmark to use (tpcds, tpch, jcch or ssb)
+    #[structopt(short, long)]
+    benchmark: String,
+
+    /// Path to the generated or converted data
+    #[structopt(parse(from_os_str), required = true, short = "i", long = "input")]
+    input_path: PathBuf,
+
+    /// Format of the data (parquet or raw). Defaults to the format recorded in the manifest of
+    /// the data set, or parquet if there is no manifest.
+    #[structopt(short, long)]
+    format: Option<Format>,
+
+    /// SQL dialect of the statements (datafusion, spark or hive). The datafusion dialect only
+    /// supports Parquet data. Tables are only declared as partitioned when their directories
+    /// contain Hive-style column=value subdirectories, which generate and convert never write.
+    #[structopt(short, long, default_value = "datafusion")]
+    dialect: String,
+
+    /// Schema profile for the column types. Defaults to the profile recorded in the manifest of
+    /// the data set, or the built-in schemas if there is no manifest.
+    #[structopt(long)]
+    schema: Option<SchemaProfile>,
+}
+
+#[derive(Debug, StructOpt)]
+struct CheckOpt {
+    /// Benchmark to use (tpcds, tpch, jcch or ssb)
+    #[structopt(short, long)]
+    benchmark: String,
+
+    /// Path to the generated or converted data
+    #[structopt(parse(from_os_str), required = true, short = "i", long = "input")]
+    input_path: PathBuf,
+
+    /// Format of the data (parquet or raw). Defaults to the format recorded in the manifest of
+    /// the data set, or parquet if there is no manifest.
+    #[structopt(short, long)]
+    format: Option<Format>,
+}
+
+#[derive(Debug, StructOpt)]
+struct VerifyOpt {
+    /// Benchmark to use (tpcds, tpch, jcch or ssb)
+    #[structopt(short, long)]
+    benchmark: String,
+
+    /// Path to the generated or converted data
+    #[structopt(parse(from_os_str), required = true, short = "i", long = "input")]
+    input_path: PathBuf,
+
+    /// Format of the data (parquet or raw). Defaults to the format recorded in the manifest of
+    /// the data set, or parquet if there is no manifest.
+    #[structopt(short, long)]
+    format: Option<Format>,
+
+    /// Scale factor the data was generated with. Defaults to the scale factor recorded in the
+    /// manifest of the data set.
+    #[structopt(short, long)]
+    scale: Option<f64>,
+}
+
+#[derive(Debug, StructOpt)]
+struct CompareOpt {
+    /// Benchmark to use (tpcds, tpch, jcch or ssb)
+    #[structopt(short, long)]
+    benchmark: String,
+
+    /// Path to the first data set
+    #[structopt(parse(from_os_str), required = true, long)]
+    left: PathBuf,
+
+    /// Path to the second data set
+    #[structopt(parse(from_os_str), required = true, long)]
+    right: PathBuf,
+
+    /// Format of the first data set (parquet or raw). Defaults to the format recorded in its
+    /// manifest, or parquet if there is no manifest.
+    #[structopt(long)]
+    left_format: Option<Format>,
+
+    /// Format of the second data set (parquet or raw). Defaults to the format recorded in its
+    /// manifest, or parquet if there is no manifest.
+    #[structopt(long)]
+    right_format: Option<Format>,
+}
+
+#[derive(Debug, StructOpt)]
+struct SetupOpt {
+    /// Benchmark to build the generator for (tpcds, tpch, jcch or ssb)
+    #[structopt(short, long)]
+    benchmark: String,
+
+    /// Checkout or source archive (.tar.gz, .tgz, .tar or .zip) of tpcds-kit, tpch-dbgen, the
+    /// JCC-H dbgen or ssb-dbgen
+    #[structopt(parse(from_os_str), required = true, short, long)]
+    source: PathBuf,
+
+    /// Directory to install the built generator in. Defaults to $XDG_CACHE_HOME/tpctools or
+    /// ~/.cache/tpctools.
+    #[structopt(parse(from_os_str), long)]
+    cache_path: Option<PathBuf>,
+
+    /// Rebuild the generator even if it has already been built from the same sources
+    #[structopt(long)]
+    force: bool,
+}
+
+#[derive(Debug, StructOpt)]
+struct BuildOpt {
+    /// TOML recipe describing the data sets to build
+    #[structopt(parse(from_os_str))]
+    recipe: PathBuf,
+
+    /// Only build the data sets with this name, which can be repeated
+    #[structopt(short, long = "dataset")]
+    datasets: Vec<String>,
+}
+
+#[derive(Debug, StructOpt)]
+enum Command {
+    Generate(GenerateOpt),
+    Convert(ConvertOpt),
+    Run(RunOpt),
+    Qphh(QphhOpt),
+    Ddl(DdlOpt),
+    Verify(VerifyOpt),
+    Check(CheckOpt),
+    Compare(CompareOpt),
+    Setup(SetupOpt),
+    Build(BuildOpt),
 }
 
 #[derive(Debug, StructOpt)]
@@ -62,58 +323,645 @@ struct ConvertOpt {
     name = "tpctools",
     about = "Tools for generating and converting TPC data sets."
 )]
-enum Opt {
-    Generate(GenerateOpt),
-    Convert(ConvertOpt),
+struct Opt {
+    /// Log more detail, including the generator commands (-v for debug, -vv for trace)
+    #[structopt(short, long, parse(from_occurrences), global = true)]
+    verbose: u8,
+
+    /// Only log warnings and errors
+    #[structopt(long, global = true)]
+    quiet: bool,
+
+    /// Format of the log written to stderr (text or json)
+    #[structopt(long, default_value = "text", global = true)]
+    log_format: LogFormat,
+
+    #[structopt(subcommand)]
+    command: Command,
 }
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    match Opt::from_args() {
-        Opt::Generate(opt) => {
-            let scale = opt.scale;
-            let partitions = opt.partitions;
-
-            if !opt.generator_path.exists() {
-                panic!(
-                    "generator path does not exist: {}",
-                    opt.generator_path.display()
-                )
-            }
+    let opt = Opt::from_args();
+    let level = match (opt.quiet, opt.verbose) {
+        (true, _) => Level::WARN,
+        (false, 0) => Level::INFO,
+        (false, 1) => Level::DEBUG,
+        (false, _) => Level::TRACE,
+    };
+    logging::init(level, opt.log_format);
 
-            if !opt.output.exists() {
-                panic!("output path does not exist: {}", opt.output.display())
+    match opt.command {
+        Command::Generate(opt) => {
+            if let Err(e) = run_generate(&opt) {
+                error!("{:?}", e);
+                std::process::exit(1);
             }
-
-            let generator_path = format!("{}", opt.generator_path.display());
-            let output_path_str = format!("{}", opt.output.display());
-
+        }
+        Command::Convert(opt) => {
+            if let Err(e) = run_convert(&opt).await {
+                error!("{:?}", e);
+                std::process::exit(1);
+            }
+        }
+        Command::Build(opt) => {
+            if let Err(e) = run_build(&opt).await {
+                error!("{:?}", e);
+                std::process::exit(1);
+            }
+        }
+        Command::Qphh(opt) => {
+            if let Err(e) = run_qphh(opt).await {
+                error!("{:?}", e);
+                std::process::exit(1);
+            }
+        }
+        Command::Ddl(opt) => {
             let tpc = create_benchmark(&opt.benchmark);
-
-            tpc.generate(scale, partitions, &generator_path, &output_path_str)?;
+            // the statements are executed from another working directory so use absolute paths
+            let input_path = opt.input_path.canonicalize()?;
+            match create_tables(
+                tpc.as_ref(),
+                input_path.to_str().unwrap(),
+                data_format(opt.format, &opt.input_path)?,
+                &opt.dialect,
+                &schema_profile(opt.schema, &opt.input_path)?,
+            ) {
+                Ok(ddl) => print!("{}", ddl),
+                Err(e) => {
+                    error!("{:?}", e);
+                    std::process::exit(1);
+                }
+            }
+        }
+        Command::Verify(opt) => {
+            let tpc = create_benchmark(&opt.benchmark);
+            let input_path = opt.input_path.as_path().to_str().unwrap();
+            let scale = match verify_scale(&opt) {
+                Ok(scale) => scale,
+                Err(e) => {
+                    error!("{:?}", e);
+                    std::process::exit(1);
+                }
+            };
+            match verify_row_counts(
+                tpc.as_ref(),
+                input_path,
+                data_format(opt.format, &opt.input_path)?,
+                scale,
+            ) {
+                Ok(counts) => {
+                    println!("{:<24} {:>14} {:>14}", "Table", "Expected", "Actual");
+                    for count in &counts {
+                        println!(
+                            "{:<24} {:>14} {:>14}{}",
+                            count.table,
+                            count
+                                .expected
+                                .map(|e| e.to_string())
+                                .unwrap_or_else(|| "-".to_owned()),
+                            count.actual,
+                            if count.has_drift() {
+                                format!(
+                                    "  DRIFT (manifest has {} rows)",
+                                    count.manifest.unwrap_or_default()
+                                )
+                            } else if count.is_valid() {
+                                String::new()
+                            } else {
+                                "  MISMATCH".to_owned()
+                            }
+                        );
+                    }
+                    let failed = counts.iter().filter(|c| !c.is_valid()).count();
+                    if failed > 0 {
+                        println!("{} tables do not have the expected number of rows", failed);
+                        std::process::exit(1);
+                    }
+                }
+                Err(e) => {
+                    error!("{:?}", e);
+                    std::process::exit(1);
+                }
+            }
         }
-        Opt::Convert(opt) => {
+        Command::Check(opt) => {
             let tpc = create_benchmark(&opt.benchmark);
-            match convert_to_parquet(
+            match check_constraints(
                 tpc.as_ref(),
                 opt.input_path.as_path().to_str().unwrap(),
-                opt.output_path.as_path().to_str().unwrap(),
+                data_format(opt.format, &opt.input_path)?,
             )
             .await
             {
-                Ok(_) => {}
-                Err(e) => println!("{:?}", e),
+                Ok(checks) => {
+                    for check in &checks {
+                        println!(
+                            "{} {}: {} violations",
+                            check.table, check.constraint, check.violations
+                        );
+                        for sample in &check.samples {
+                            println!("    {}", sample);
+                        }
+                    }
+                    let failed = checks.iter().filter(|c| c.violations > 0).count();
+                    if failed > 0 {
+                        println!("{} constraints were violated", failed);
+                        std::process::exit(1);
+                    }
+                }
+                Err(e) => {
+                    error!("{:?}", e);
+                    std::process::exit(1);
+                }
+            }
+        }
+        Command::Compare(opt) => {
+            let tpc = create_benchmark(&opt.benchmark);
+            match compare_datasets(
+                tpc.as_ref(),
+                opt.left.as_path().to_str().unwrap(),
+                data_format(opt.left_format, &opt.left)?,
+                opt.right.as_path().to_str().unwrap(),
+                data_format(opt.right_format, &opt.right)?,
+            )
+            .await
+            {
+                Ok(comparisons) => {
+                    println!(
+                        "{:<24} {:>14} {:>14} {:>18} {:>18}",
+                        "Table", "Left rows", "Right rows", "Left checksum", "Right checksum"
+                    );
+                    for c in &comparisons {
+                        println!(
+                            "{:<24} {:>14} {:>14}   {:016x}   {:016x}{}",
+                            c.table,
+                            c.left.rows,
+                            c.right.rows,
+                            c.left.checksum,
+                            c.right.checksum,
+                            if c.is_match() { "" } else { "  DIFFERENT" }
+                        );
+                    }
+                    let different = comparisons.iter().filter(|c| !c.is_match()).count();
+                    if different > 0 {
+                        println!("{} tables are different", different);
+                        std::process::exit(1);
+                    }
+                }
+                Err(e) => {
+                    error!("{:?}", e);
+                    std::process::exit(1);
+                }
+            }
+        }
+        Command::Setup(opt) => {
+            let cache_path = match opt.cache_path {
+                Some(path) => path,
+                None => default_cache_path()?,
+            };
+            let tpc = create_benchmark(&opt.benchmark);
+            match setup_generator(
+                tpc.as_ref(),
+                &opt.benchmark,
+                &opt.source,
+                &cache_path,
+                opt.force,
+            ) {
+                Ok(path) => println!("Use --generator-path {}", path.display()),
+                Err(e) => {
+                    error!("{:?}", e);
+                    std::process::exit(1);
+                }
+            }
+        }
+        Command::Run(opt) => {
+            let tpc = create_benchmark(&opt.benchmark);
+            match run_queries(
+                tpc.as_ref(),
+                &opt.benchmark,
+                opt.input_path.as_path().to_str().unwrap(),
+                data_format(opt.format, &opt.input_path)?,
+                opt.query_path.as_path().to_str().unwrap(),
+                opt.iterations,
+                opt.answers_path.as_ref().map(|p| p.to_str().unwrap()),
+            )
+            .await
+            {
+                Ok(summary) => {
+                    println!(
+                        "{:<10} {:>12} {:>12} {:>12} {:>10}",
+                        "Query", "Min (ms)", "Avg (ms)", "Max (ms)", "Rows"
+                    );
+                    for q in &summary.queries {
+                        println!(
+                            "{:<10} {:>12.1} {:>12.1} {:>12.1} {:>10}{}",
+                            q.query,
+                            q.min_ms,
+                            q.avg_ms,
+                            q.max_ms,
+                            q.rows,
+                            match &q.differences {
+                                Some(d) if d.is_empty() => " PASS",
+                                Some(_) => " FAIL",
+                                None => "",
+                            }
+                        );
+                    }
+                    if let Some(output_path) = &opt.output_path {
+                        match write_summary(&summary, output_path) {
+                            Ok(path) => info!(path = %path.display(), "Wrote results"),
+                            Err(e) => {
+                                error!("{:?}", e);
+                                std::process::exit(1);
+                            }
+                        }
+                    }
+                    let failed = summary
+                        .queries
+                        .iter()
+                        .filter(|q| q.differences.iter().any(|d| !d.is_empty()))
+                        .count();
+                    if failed > 0 {
+                        println!("{} queries did not match the reference answers", failed);
+                        std::process::exit(1);
+                    }
+                }
+                Err(e) => {
+                    error!("{:?}", e);
+                    std::process::exit(1);
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn run_generate(opt: &GenerateOpt) -> datafusion::error::Result<()> {
+    let scale = opt.scale;
+    let partitions = opt.partitions;
+
+    let invalid = |message: String| Err(DataFusionError::Execution(message));
+    let is_tpch = matches!(opt.benchmark.as_str(), "tpch" | "tpc-h");
+    if !matches!(
+        opt.benchmark.as_str(),
+        "tpcds" | "tpc-ds" | "tpch" | "tpc-h" | "jcch" | "jcc-h" | "ssb"
+    ) {
+        return invalid(format!("Invalid benchmark name: {}", opt.benchmark));
+    }
+    check_scale(scale)?;
+    match &opt.generator_path {
+        Some(generator_path) if !generator_path.exists() => {
+            return invalid(format!(
+                "Generator path does not exist: {}",
+                generator_path.display()
+            ))
+        }
+        Some(_)
+            if opt.format == Format::Parquet
+                && !matches!(opt.benchmark.as_str(), "tpcds" | "tpc-ds") =>
+        {
+            return invalid(
+                "--format parquet is only supported for TPC-DS, or for TPC-H without --generator-path"
+                    .to_owned(),
+            )
+        }
+        Some(_) if opt.seed.is_some() && !matches!(opt.benchmark.as_str(), "tpcds" | "tpc-ds") => {
+            return invalid(
+                "--seed is only supported for TPC-DS, or for TPC-H without --generator-path"
+                    .to_owned(),
+            )
+        }
+        None if opt.refresh_sets > 0 => {
+            return invalid("--generator-path is required to generate refresh data".to_owned())
+        }
+        None if !is_tpch => {
+            return invalid(format!(
+                "--generator-path is required for {}",
+                opt.benchmark
+            ))
+        }
+        _ => {}
+    }
+    if opt.refresh_sets > 0 && !is_tpch {
+        return invalid("Refresh data can only be generated for TPC-H".to_owned());
+    }
+
+    if !opt.output.exists() {
+        return invalid(format!(
+            "Output path does not exist: {}",
+            opt.output.display()
+        ));
+    }
+
+    let output_path_str = format!("{}", opt.output.display());
+
+    let tpc = create_benchmark(&opt.benchmark);
+
+    let start = Instant::now();
+    let recorder = TimingRecorder::new();
+    let progress = recorder.record(&Progress::terminal());
+    let _span = info_span!(
+        "generate",
+        benchmark = %opt.benchmark,
+        scale,
+        partitions
+    )
+    .entered();
+
+    let generator = match &opt.generator_path {
+        Some(generator_path) => {
+            let generator_path = format!("{}", generator_path.display());
+            if opt.format == Format::Parquet {
+                TpcDs::new().generate_parquet(
+                    scale,
+                    partitions,
+                    &generator_path,
+                    &output_path_str,
+                    opt.seed,
+                    &progress,
+                )?;
+            } else {
+                tpc.generate(
+                    scale,
+                    partitions,
+                    &generator_path,
+                    &output_path_str,
+                    opt.seed,
+                    &progress,
+                )?;
+            }
+            let name = if matches!(opt.benchmark.as_str(), "tpcds" | "tpc-ds") {
+                "dsdgen"
+            } else {
+                "dbgen"
+            };
+            GeneratorInfo::new(name, &generator_path)?
+        }
+        None => {
+            match opt.format {
+                Format::Raw => TpcH::new().generate_native(
+                    scale,
+                    partitions,
+                    &output_path_str,
+                    opt.seed,
+                    &progress,
+                )?,
+                Format::Parquet => TpcH::new().generate_parquet(
+                    scale,
+                    partitions,
+                    &output_path_str,
+                    opt.seed,
+                    &progress,
+                )?,
             }
+            GeneratorInfo::native()
+        }
+    };
+    let elapsed = start.elapsed();
+
+    if let Some(generator_path) = opt.generator_path.as_ref().filter(|_| opt.refresh_sets > 0) {
+        let generator_path = format!("{}", generator_path.display());
+        let refresh_path = format!("{}/refresh", output_path_str);
+        TpcH::new().generate_refresh_data(
+            scale,
+            opt.refresh_sets,
+            &generator_path,
+            &refresh_path,
+        )?;
+    }
+
+    let mut manifest = Manifest::scan(
+        tpc.as_ref(),
+        &opt.benchmark,
+        &output_path_str,
+        opt.format,
+        &SchemaProfile::default(),
+    )?;
+    manifest.scale = Some(scale);
+    manifest.partitions = Some(partitions);
+    manifest.generator = Some(generator);
+    manifest.seed = opt.seed;
+    if opt.format == Format::Parquet {
+        manifest.compression = Some("snappy".to_owned());
+    }
+    manifest.elapsed_secs = elapsed.as_secs_f64();
+    manifest.write(&output_path_str)?;
+    write_timing_report(&recorder, "generate", &manifest, opt.report.as_deref())
+}
+
+async fn run_convert(opt: &ConvertOpt) -> datafusion::error::Result<()> {
+    let tpc = create_benchmark(&opt.benchmark);
+    let input_path = opt.input_path.as_path().to_str().unwrap();
+    let output_path = opt.output_path.as_path().to_str().unwrap();
+    let start = Instant::now();
+    let recorder = TimingRecorder::new();
+    convert_to_parquet(
+        tpc.as_ref(),
+        input_path,
+        output_path,
+        &opt.schema,
+        &opt.compression,
+        &recorder.record(&Progress::terminal()),
+    )
+    .await?;
+
+    let mut manifest = Manifest::scan(
+        tpc.as_ref(),
+        &opt.benchmark,
+        output_path,
+        Format::Parquet,
+        &opt.schema,
+    )?;
+    if let Some(source) = Manifest::read(input_path)? {
+        manifest.scale = source.scale;
+        manifest.partitions = source.partitions;
+        manifest.generator = source.generator;
+        manifest.seed = source.seed;
+    }
+    manifest.compression = Some(opt.compression.clone());
+    manifest.source_path = Some(input_path.to_owned());
+    manifest.elapsed_secs = start.elapsed().as_secs_f64();
+    manifest.write(output_path)?;
+    write_timing_report(&recorder, "convert", &manifest, opt.report.as_deref())
+}
+
+/// Generate each data set of a recipe and convert it to Parquet, skipping the steps that have
+/// already completed, which is when their output has a manifest
+async fn run_build(opt: &BuildOpt) -> datafusion::error::Result<()> {
+    let recipe = Recipe::read(&opt.recipe)?;
+    for name in &opt.datasets {
+        if !recipe.datasets.iter().any(|d| &d.name == name) {
+            return Err(DataFusionError::Execution(format!(
+                "Dataset {} is not defined in {}",
+                name,
+                opt.recipe.display()
+            )));
         }
     }
 
+    for dataset in &recipe.datasets {
+        if !opt.datasets.is_empty() && !opt.datasets.contains(&dataset.name) {
+            continue;
+        }
+        let span = info_span!("build", dataset = %dataset.name);
+        let dataset_path = recipe.dataset_path(dataset);
+        let raw_path = recipe.raw_path(dataset);
+
+        if let Some(manifest) = read_manifest(&raw_path)? {
+            recipe.check_manifest(dataset, &manifest)?;
+            span.in_scope(|| info!(path = %raw_path.display(), "Already generated"));
+        } else {
+            check_empty(&raw_path)?;
+            fs::create_dir_all(&raw_path)?;
+            span.in_scope(|| {
+                run_generate(&GenerateOpt {
+                    benchmark: dataset.benchmark.clone(),
+                    scale: dataset.scale,
+                    partitions: dataset.partitions,
+                    generator_path: dataset.generator_path.clone(),
+                    output: raw_path.clone(),
+                    format: Format::Raw,
+                    refresh_sets: dataset.refresh_sets,
+                    seed: dataset.seed,
+                    report: Some(dataset_path.join("generate-report.json")),
+                })
+            })?;
+        }
+
+        if let Some(parquet_path) = recipe.parquet_path(dataset) {
+            if let Some(manifest) = read_manifest(&parquet_path)? {
+                recipe.check_manifest(dataset, &manifest)?;
+                span.in_scope(|| info!(path = %parquet_path.display(), "Already converted"));
+                continue;
+            }
+            check_empty(&parquet_path)?;
+            fs::create_dir_all(&parquet_path)?;
+            run_convert(&ConvertOpt {
+                benchmark: dataset.benchmark.clone(),
+                input_path: raw_path,
+                output_path: parquet_path,
+                schema: dataset.schema.clone(),
+                compression: dataset.compression.clone(),
+                report: Some(dataset_path.join("convert-report.json")),
+            })
+            .instrument(span)
+            .await?;
+        }
+    }
     Ok(())
 }
 
+/// Steps that did not complete leave files without a manifest behind, which are not removed
+/// automatically in case the path is wrong
+fn check_empty(path: &Path) -> datafusion::error::Result<()> {
+    if path.exists() && fs::read_dir(path)?.next().is_some() {
+        return Err(DataFusionError::Execution(format!(
+            "{} is not empty but has no manifest, remove it to build it again",
+            path.display()
+        )));
+    }
+    Ok(())
+}
+
+async fn run_qphh(opt: QphhOpt) -> datafusion::error::Result<()> {
+    let streams = opt.streams.unwrap_or_else(|| min_streams(opt.scale));
+    let generator = opt.qgen_path.as_ref().map(|path| {
+        let seed = opt.seed.unwrap_or_else(|| {
+            Utc::now()
+                .format("%m%d%H%M%S")
+                .to_string()
+                .parse()
+                .unwrap_or_default()
+        });
+        info!(seed, "Generating queries with qgen");
+        QueryGenerator {
+            generator_path: path.display().to_string(),
+            seed,
+        }
+    });
+    let report = run_performance_test(
+        opt.input_path.as_path().to_str().unwrap(),
+        opt.refresh_path.as_path().to_str().unwrap(),
+        opt.query_path.as_path().to_str().unwrap(),
+        opt.scale,
+        streams,
+        generator.as_ref(),
+    )
+    .await?;
+    if let Some(output_path) = &opt.output_path {
+        write_report(&report, output_path)?;
+        info!(path = %output_path.display(), "Wrote report");
+    }
+    Ok(())
+}
+
+fn write_timing_report(
+    recorder: &TimingRecorder,
+    command: &str,
+    manifest: &Manifest,
+    path: Option<&Path>,
+) -> datafusion::error::Result<()> {
+    if let Some(path) = path {
+        recorder.report(command, manifest).write(path)?;
+        info!(path = %path.display(), "Wrote report");
+    }
+    Ok(())
+}
+
+/// The scale factor to verify against, which defaults to the one recorded in the manifest
+fn verify_scale(opt: &VerifyOpt) -> Result<f64> {
+    if let Some(scale) = opt.scale {
+        return Ok(scale);
+    }
+    read_manifest(&opt.input_path)?
+        .and_then(|m| m.scale)
+        .ok_or_else(|| {
+            Error::new(
+                ErrorKind::InvalidInput,
+                "--scale is required when the data set has no manifest",
+            )
+        })
+}
+
+fn read_manifest(data_path: &Path) -> Result<Option<Manifest>> {
+    Manifest::read(data_path.to_str().unwrap()).map_err(|e| std::io::Error::other(e.to_string()))
+}
+
+/// Use the format recorded in the manifest of the data set when no format is specified
+fn data_format(format: Option<Format>, data_path: &Path) -> Result<Format> {
+    if let Some(format) = format {
+        return Ok(format);
+    }
+    Ok(match read_manifest(data_path)? {
+        Some(manifest) => manifest
+            .format
+            .parse()
+            .map_err(|e: DataFusionError| std::io::Error::other(e.to_string()))?,
+        None => Format::Parquet,
+    })
+}
+
+/// Use the schema profile recorded in the manifest of the data set when no profile is specified
+fn schema_profile(profile: Option<SchemaProfile>, data_path: &Path) -> Result<SchemaProfile> {
+    if let Some(profile) = profile {
+        return Ok(profile);
+    }
+    match read_manifest(data_path)?.and_then(|m| m.schema_profile) {
+        Some(profile) => profile
+            .parse()
+            .map_err(|e: DataFusionError| std::io::Error::other(e.to_string())),
+        None => Ok(SchemaProfile::default()),
+    }
+}
+
 fn create_benchmark(name: &str) -> Box<dyn Tpc> {
     match name {
         "tpcds" | "tpc-ds" => Box::new(TpcDs::new()),
         "tpch" | "tpc-h" => Box::new(TpcH::new()),
+        "jcch" | "jcc-h" => Box::new(TpcH::skewed()),
+        "ssb" => Box::new(Ssb::new()),
         _ => panic!("invalid benchmark name"),
     }
 }
@@ -0,0 +1,256 @@
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::fs;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use datafusion::error::{DataFusionError, Result};
+use serde::{Deserialize, Serialize};
+use tracing::info;
+
+use crate::profile::SchemaProfile;
+use crate::verify::count_rows;
+use crate::{Format, Tpc};
+
+/// Name of the manifest file written at the root of every data set
+pub const MANIFEST_FILE: &str = "manifest.json";
+
+/// Description of how a data set was produced and what it contains, written as `manifest.json`
+/// by `generate` and `convert`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Manifest {
+    pub benchmark: String,
+    pub scale: Option<f64>,
+    pub partitions: Option<usize>,
+    pub generator: Option<GeneratorInfo>,
+    /// Seed passed to the generator, or `None` if it used its default seed
+    pub seed: Option<u64>,
+    pub tpctools_version: String,
+    pub format: String,
+    pub compression: Option<String>,
+    /// Schema profile of the column types, see `SchemaProfile`
+    pub schema_profile: Option<String>,
+    /// Data set this one was converted from, for the output of `convert`
+    pub source_path: Option<String>,
+    pub created_time: u64,
+    pub elapsed_secs: f64,
+    pub tables: Vec<TableManifest>,
+}
+
+/// The data generator that produced the data
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GeneratorInfo {
+    /// `dbgen`, `dsdgen` or `native` for the TPC-H generator in this crate
+    pub name: String,
+    pub path: Option<String>,
+    /// FNV-1a hash of the generator executable, to tell different builds apart
+    pub hash: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TableManifest {
+    pub name: String,
+    /// Number of rows, when it is known for all files
+    pub rows: Option<u64>,
+    pub columns: Vec<ColumnManifest>,
+    pub files: Vec<FileManifest>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ColumnManifest {
+    pub name: String,
+    pub data_type: String,
+    pub nullable: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileManifest {
+    /// Path relative to the root of the data set
+    pub path: String,
+    pub size: u64,
+    /// Number of rows, which is read from the metadata of Parquet files. Raw files are not
+    /// counted, since that would read the whole data set again, see `verify`.
+    pub rows: Option<u64>,
+}
+
+impl Manifest {
+    /// Describe the data set at `data_path` by listing the files of every table. The details of
+    /// how the data was produced are left empty for the caller to fill in.
+    pub fn scan(
+        benchmark: &dyn Tpc,
+        name: &str,
+        data_path: &str,
+        format: Format,
+        profile: &SchemaProfile,
+    ) -> Result<Self> {
+        let mut tables = vec![];
+        for table in benchmark.get_table_names() {
+            let path = format.table_path(benchmark, data_path, table);
+            let mut files = vec![];
+            list_files(Path::new(data_path), Path::new(&path), format, &mut files)?;
+            files.sort_by(|a, b| a.path.cmp(&b.path));
+            let columns = profile
+                .get_schema(benchmark, table)?
+                .fields()
+                .iter()
+                .map(|f| ColumnManifest {
+                    name: f.name().to_owned(),
+                    data_type: f.data_type().to_string(),
+                    nullable: f.is_nullable(),
+                })
+                .collect();
+            tables.push(TableManifest {
+                name: table.to_owned(),
+                rows: files.iter().map(|f| f.rows).sum::<Option<u64>>(),
+                columns,
+                files,
+            });
+        }
+        Ok(Self {
+            benchmark: name.to_owned(),
+            scale: None,
+            partitions: None,
+            generator: None,
+            seed: None,
+            tpctools_version: env!("CARGO_PKG_VERSION").to_owned(),
+            format: format.to_string(),
+            compression: None,
+            schema_profile: Some(profile.to_string()),
+            source_path: None,
+            created_time: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or_default(),
+            elapsed_secs: 0.0,
+            tables,
+        })
+    }
+
+    /// Read the manifest of a data set, if it has one
+    pub fn read(data_path: &str) -> Result<Option<Self>> {
+        let path = Path::new(data_path).join(MANIFEST_FILE);
+        if !path.exists() {
+            return Ok(None);
+        }
+        let json = fs::read_to_string(&path)?;
+        let manifest = serde_json::from_str(&json).map_err(|e| {
+            DataFusionError::Execution(format!("Failed to parse {}: {}", path.display(), e))
+        })?;
+        Ok(Some(manifest))
+    }
+
+    pub fn write(&self, data_path: &str) -> Result<()> {
+        let path = Path::new(data_path).join(MANIFEST_FILE);
+        let json = serde_json::to_string_pretty(self).map_err(|e| {
+            DataFusionError::Execution(format!("Failed to serialize manifest: {}", e))
+        })?;
+        fs::write(&path, json)?;
+        info!(path = %path.display(), "Wrote manifest");
+        Ok(())
+    }
+
+    pub fn table(&self, name: &str) -> Option<&TableManifest> {
+        self.tables.iter().find(|t| t.name == name)
+    }
+}
+
+impl GeneratorInfo {
+    /// Describe the external generator executable in `generator_path`
+    pub fn new(name: &str, generator_path: &str) -> Result<Self> {
+        let binary = Path::new(generator_path).join(name);
+        let hash = if binary.exists() {
+            Some(format!("{:016x}", fnv1a(&fs::read(&binary)?)))
+        } else {
+            None
+        };
+        Ok(Self {
+            name: name.to_owned(),
+            path: Some(generator_path.to_owned()),
+            hash,
+        })
+    }
+
+    /// The TPC-H generator in this crate
+    pub fn native() -> Self {
+        Self {
+            name: "native".to_owned(),
+            path: None,
+            hash: None,
+        }
+    }
+}
+
+fn list_files(
+    root: &Path,
+    path: &Path,
+    format: Format,
+    files: &mut Vec<FileManifest>,
+) -> Result<()> {
+    if !path.exists() {
+        return Ok(());
+    }
+    if path.is_dir() {
+        for entry in fs::read_dir(path)? {
+            list_files(root, &entry?.path(), format, files)?;
+        }
+        return Ok(());
+    }
+    let relative = path.strip_prefix(root).unwrap_or(path);
+    files.push(FileManifest {
+        path: relative.display().to_string(),
+        size: fs::metadata(path)?.len(),
+        rows: match format {
+            Format::Parquet => Some(count_rows(path, format)?),
+            Format::Raw => None,
+        },
+    });
+    Ok(())
+}
+
+/// 64 bit FNV-1a hash, which is stable across platforms and Rust versions
+pub(crate) fn fnv1a(bytes: &[u8]) -> u64 {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for b in bytes {
+        hash ^= *b as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    hash
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::progress::Progress;
+    use crate::tpch::TpcH;
+
+    #[test]
+    fn rows_are_only_recorded_for_parquet() -> Result<()> {
+        let raw = tempfile::tempdir()?;
+        let parquet = tempfile::tempdir()?;
+        let raw_path = raw.path().to_str().unwrap();
+        let parquet_path = parquet.path().to_str().unwrap();
+        let tpch = TpcH::new();
+        tpch.generate_native(0.001, 1, raw_path, None, &Progress::none())?;
+        tpch.generate_parquet(0.001, 1, parquet_path, None, &Progress::none())?;
+        let profile = SchemaProfile::default();
+
+        let manifest = Manifest::scan(&tpch, "tpch", raw_path, Format::Raw, &profile)?;
+        let nation = manifest.table("nation").unwrap();
+        assert_eq!(1, nation.files.len());
+        assert_eq!(None, nation.rows);
+
+        let manifest = Manifest::scan(&tpch, "tpch", parquet_path, Format::Parquet, &profile)?;
+        assert_eq!(Some(25), manifest.table("nation").unwrap().rows);
+        Ok(())
+    }
+}
@@ -0,0 +1,621 @@
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! TPC-H power and throughput tests, and the Power@Size, Throughput@Size and QphH@Size
+//! metrics as defined in section 5.4 of the TPC-H specification.
+//!
+//! The refresh functions modify the dataset in place, so the tests should be run against a
+//! copy of the converted Parquet data. New and rewritten files are prepared outside of the table
+//! directories and only moved into place while no query is running, so that concurrent query
+//! streams always see complete files.
+//!
+//! Each query stream uses its own substitution parameters, which are either generated with
+//! `qgen` or read from a subdirectory of the query path per stream, see `load_query_sets`.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::Arc;
+use std::time::Instant;
+
+use tokio::sync::RwLock;
+
+use datafusion::arrow::datatypes::{DataType, Field, Schema};
+use datafusion::error::{DataFusionError, Result};
+use datafusion::prelude::*;
+use serde::Serialize;
+use tracing::{info, warn};
+
+use crate::runner::execute_query;
+use crate::tpch::TpcH;
+use crate::{register_tables, Format, Tpc};
+
+/// Query order for each query stream, from Appendix A of the TPC-H specification. Stream 0 is
+/// used by the power test and streams 1 to S by the throughput test.
+#[rustfmt::skip]
+const QUERY_STREAMS: [[usize; 22]; 41] = [
+    [14, 2, 9, 20, 6, 17, 18, 8, 21, 13, 3, 22, 16, 4, 11, 15, 1, 10, 19, 5, 7, 12],
+    [21, 3, 18, 5, 11, 7, 6, 20, 17, 12, 16, 15, 13, 10, 2, 8, 14, 19, 9, 22, 1, 4],
+    [6, 17, 14, 16, 19, 10, 9, 2, 15, 8, 5, 22, 12, 7, 13, 18, 1, 4, 20, 3, 11, 21],
+    [8, 5, 4, 6, 17, 7, 1, 18, 22, 14, 9, 10, 15, 11, 20, 2, 21, 19, 13, 16, 12, 3],
+    [5, 21, 14, 19, 15, 17, 12, 6, 4, 9, 8, 16, 11, 2, 10, 18, 1, 13, 7, 22, 3, 20],
+    [21, 15, 4, 6, 7, 16, 19, 18, 14, 22, 11, 13, 3, 1, 2, 5, 8, 20, 12, 17, 10, 9],
+    [10, 3, 15, 13, 6, 8, 9, 7, 4, 11, 22, 18, 12, 1, 5, 16, 2, 14, 19, 20, 17, 21],
+    [18, 8, 20, 21, 2, 4, 22, 17, 1, 11, 9, 19, 3, 13, 5, 7, 10, 16, 6, 14, 15, 12],
+    [19, 1, 15, 17, 5, 8, 9, 12, 14, 7, 4, 3, 20, 16, 6, 22, 10, 13, 2, 21, 18, 11],
+    [8, 13, 2, 20, 17, 3, 6, 21, 18, 11, 19, 10, 15, 4, 22, 1, 7, 12, 9, 14, 5, 16],
+    [6, 15, 18, 17, 12, 1, 7, 2, 22, 13, 21, 10, 14, 9, 3, 16, 20, 19, 11, 4, 8, 5],
+    [15, 14, 18, 17, 10, 20, 16, 11, 1, 8, 4, 22, 5, 12, 3, 9, 21, 2, 13, 6, 19, 7],
+    [1, 7, 16, 17, 18, 22, 12, 6, 8, 9, 11, 4, 2, 5, 20, 21, 13, 10, 19, 3, 14, 15],
+    [21, 17, 7, 3, 1, 10, 12, 22, 9, 16, 6, 11, 2, 4, 5, 14, 8, 20, 13, 18, 15, 19],
+    [2, 9, 5, 4, 18, 1, 20, 15, 16, 17, 7, 21, 13, 14, 19, 8, 22, 11, 10, 3, 12, 6],
+    [16, 9, 17, 8, 14, 11, 10, 12, 6, 21, 7, 3, 15, 5, 22, 20, 1, 13, 19, 2, 4, 18],
+    [1, 3, 6, 5, 2, 16, 14, 22, 17, 20, 4, 9, 10, 11, 15, 8, 12, 19, 18, 13, 7, 21],
+    [3, 16, 5, 11, 21, 9, 2, 15, 10, 18, 17, 7, 8, 19, 14, 13, 1, 4, 22, 20, 6, 12],
+    [14, 4, 13, 5, 21, 11, 8, 6, 3, 17, 2, 20, 1, 19, 10, 9, 12, 18, 15, 7, 22, 16],
+    [4, 12, 22, 14, 5, 15, 16, 2, 8, 10, 17, 9, 21, 7, 3, 6, 13, 18, 11, 20, 19, 1],
+    [16, 15, 14, 13, 4, 22, 18, 19, 7, 1, 12, 17, 5, 10, 20, 3, 9, 21, 11, 2, 6, 8],
+    [20, 14, 21, 12, 15, 17, 4, 19, 13, 10, 11, 1, 16, 5, 18, 7, 8, 22, 9, 6, 3, 2],
+    [16, 14, 13, 2, 21, 10, 11, 4, 1, 22, 18, 12, 19, 5, 7, 8, 6, 3, 15, 20, 9, 17],
+    [18, 15, 9, 14, 12, 2, 8, 11, 22, 21, 16, 1, 6, 17, 5, 10, 19, 4, 20, 13, 3, 7],
+    [7, 3, 10, 14, 13, 21, 18, 6, 20, 4, 9, 8, 22, 15, 2, 1, 5, 12, 19, 17, 11, 16],
+    [18, 1, 13, 7, 16, 10, 14, 2, 19, 5, 21, 11, 22, 15, 8, 17, 20, 3, 4, 12, 6, 9],
+    [13, 2, 22, 5, 11, 21, 20, 14, 7, 10, 4, 9, 19, 18, 6, 3, 1, 8, 15, 12, 17, 16],
+    [14, 17, 21, 8, 2, 9, 6, 4, 5, 13, 22, 7, 15, 3, 1, 18, 16, 11, 10, 12, 20, 19],
+    [10, 22, 1, 12, 13, 18, 21, 20, 2, 14, 16, 7, 15, 3, 4, 17, 5, 19, 6, 8, 9, 11],
+    [10, 8, 9, 18, 12, 6, 1, 5, 20, 11, 17, 22, 16, 3, 13, 2, 15, 21, 14, 19, 7, 4],
+    [7, 17, 22, 5, 3, 10, 13, 18, 9, 1, 14, 15, 21, 19, 16, 12, 8, 6, 11, 20, 4, 2],
+    [2, 9, 21, 3, 4, 7, 1, 11, 16, 5, 20, 19, 18, 8, 17, 13, 10, 12, 15, 6, 14, 22],
+    [15, 12, 8, 4, 22, 13, 16, 17, 18, 3, 7, 5, 6, 1, 9, 11, 21, 10, 14, 20, 19, 2],
+    [15, 16, 2, 11, 17, 7, 5, 14, 20, 4, 21, 3, 10, 9, 12, 8, 13, 6, 18, 19, 22, 1],
+    [1, 13, 11, 3, 4, 21, 6, 14, 15, 22, 18, 9, 7, 5, 10, 20, 12, 16, 17, 8, 19, 2],
+    [14, 17, 22, 20, 8, 16, 5, 10, 1, 13, 2, 21, 12, 9, 4, 18, 3, 7, 6, 19, 15, 11],
+    [9, 17, 7, 4, 5, 13, 21, 18, 11, 3, 22, 1, 6, 16, 20, 14, 15, 10, 8, 2, 12, 19],
+    [13, 14, 5, 22, 19, 11, 9, 6, 18, 15, 8, 10, 7, 4, 17, 16, 3, 1, 12, 2, 21, 20],
+    [20, 5, 4, 14, 11, 1, 6, 16, 8, 22, 7, 3, 2, 12, 21, 19, 17, 13, 10, 15, 18, 9],
+    [3, 7, 14, 15, 6, 5, 21, 20, 18, 10, 4, 16, 19, 1, 13, 9, 8, 17, 11, 12, 22, 2],
+    [13, 15, 17, 1, 22, 11, 3, 4, 7, 20, 14, 21, 9, 8, 2, 18, 16, 6, 10, 12, 5, 19],
+];
+
+/// Elapsed time of a single query or refresh function, in seconds
+#[derive(Debug, Serialize)]
+pub struct Timing {
+    pub name: String,
+    pub seconds: f64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PowerTestResult {
+    pub rf1_seconds: f64,
+    pub queries: Vec<Timing>,
+    pub rf2_seconds: f64,
+    pub power_at_size: f64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct QueryStreamResult {
+    pub stream: usize,
+    pub seconds: f64,
+    pub queries: Vec<Timing>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RefreshResult {
+    pub update_set: usize,
+    pub rf1_seconds: f64,
+    pub rf2_seconds: f64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ThroughputTestResult {
+    pub seconds: f64,
+    pub query_streams: Vec<QueryStreamResult>,
+    pub refresh_stream: Vec<RefreshResult>,
+    pub throughput_at_size: f64,
+}
+
+/// Results of a TPC-H performance test run, written out as JSON
+#[derive(Debug, Serialize)]
+pub struct MetricReport {
+    pub scale: f64,
+    pub streams: usize,
+    pub power_test: PowerTestResult,
+    pub throughput_test: ThroughputTestResult,
+    pub qphh_at_size: f64,
+}
+
+/// Generates the queries of each stream with `qgen`
+#[derive(Debug, Clone)]
+pub struct QueryGenerator {
+    /// Directory containing `qgen` and `dists.dss`, such as the one built by `setup`
+    pub generator_path: String,
+    /// Seed of the substitution parameters of the power test. Query stream `s` of the throughput
+    /// test uses `seed + s`.
+    pub seed: u64,
+}
+
+/// Minimum number of query streams for the throughput test at a given scale factor
+pub fn min_streams(scale: f64) -> usize {
+    const THRESHOLDS: [f64; 9] = [
+        10.0, 30.0, 100.0, 300.0, 1000.0, 3000.0, 10000.0, 30000.0, 100000.0,
+    ];
+    2 + THRESHOLDS.iter().filter(|t| scale >= **t).count()
+}
+
+/// Run the power test followed by the throughput test against converted TPC-H Parquet data.
+///
+/// `refresh_path` must contain `streams + 1` update sets generated with
+/// `TpcH::generate_refresh_data`. The queries of each stream are loaded from `query_path` by
+/// `load_query_sets`.
+pub async fn run_performance_test(
+    data_path: &str,
+    refresh_path: &str,
+    query_path: &str,
+    scale: f64,
+    streams: usize,
+    generator: Option<&QueryGenerator>,
+) -> Result<MetricReport> {
+    if streams == 0 || streams >= QUERY_STREAMS.len() {
+        return Err(DataFusionError::Plan(format!(
+            "The number of query streams must be between 1 and {}",
+            QUERY_STREAMS.len() - 1
+        )));
+    }
+
+    let query_sets = load_query_sets(query_path, scale, streams, generator)?;
+
+    let ctx = SessionContext::new();
+    register_tables(&ctx, &TpcH::new(), data_path, Format::Parquet).await?;
+    let lock = Arc::new(RwLock::new(()));
+
+    info!("Starting power test");
+    let rf1_seconds = refresh_insert(&ctx, &lock, data_path, refresh_path, 1).await?;
+    let power_queries = run_query_stream(&ctx, &lock, &query_sets[0], 0).await?;
+    let rf2_seconds = refresh_delete(&ctx, &lock, data_path, refresh_path, 1).await?;
+    let power_at_size = power_at_size(scale, &power_queries, rf1_seconds, rf2_seconds);
+    info!(power_at_size, "Power@Size = {:.1}", power_at_size);
+
+    info!(streams, "Starting throughput test");
+    let start = Instant::now();
+    let mut handles = vec![];
+    for (stream, queries) in query_sets.into_iter().enumerate().skip(1) {
+        let ctx = ctx.clone();
+        let lock = lock.clone();
+        handles.push(tokio::spawn(async move {
+            let start = Instant::now();
+            let queries = run_query_stream(&ctx, &lock, &queries, stream).await?;
+            Ok::<_, DataFusionError>(QueryStreamResult {
+                stream,
+                seconds: start.elapsed().as_secs_f64(),
+                queries,
+            })
+        }));
+    }
+    let refresh_ctx = ctx.clone();
+    let refresh_lock = lock.clone();
+    let data_path_owned = data_path.to_owned();
+    let refresh_path_owned = refresh_path.to_owned();
+    let refresh_handle = tokio::spawn(async move {
+        let mut results = vec![];
+        for update_set in 2..=streams + 1 {
+            let rf1_seconds = refresh_insert(
+                &refresh_ctx,
+                &refresh_lock,
+                &data_path_owned,
+                &refresh_path_owned,
+                update_set,
+            )
+            .await?;
+            let rf2_seconds = refresh_delete(
+                &refresh_ctx,
+                &refresh_lock,
+                &data_path_owned,
+                &refresh_path_owned,
+                update_set,
+            )
+            .await?;
+            results.push(RefreshResult {
+                update_set,
+                rf1_seconds,
+                rf2_seconds,
+            });
+        }
+        Ok::<_, DataFusionError>(results)
+    });
+
+    let mut query_streams = vec![];
+    for handle in handles {
+        query_streams.push(join(handle).await??);
+    }
+    let refresh_stream = join(refresh_handle).await??;
+    let seconds = start.elapsed().as_secs_f64();
+    let throughput_at_size = (streams * 22 * 3600) as f64 / seconds * scale;
+    info!(
+        throughput_at_size,
+        "Throughput@Size = {:.1}", throughput_at_size
+    );
+
+    let qphh_at_size = (power_at_size * throughput_at_size).sqrt();
+    info!(qphh_at_size, "QphH@Size = {:.1}", qphh_at_size);
+
+    Ok(MetricReport {
+        scale,
+        streams,
+        power_test: PowerTestResult {
+            rf1_seconds,
+            queries: power_queries,
+            rf2_seconds,
+            power_at_size,
+        },
+        throughput_test: ThroughputTestResult {
+            seconds,
+            query_streams,
+            refresh_stream,
+            throughput_at_size,
+        },
+        qphh_at_size,
+    })
+}
+
+/// Load the 22 queries of the power test (stream 0) and of each query stream of the throughput
+/// test, as `q1.sql` to `q22.sql`.
+///
+/// With a `generator`, `query_path` contains the `qgen` templates `1.sql` to `22.sql` and the
+/// queries of each stream are generated with the seed of the stream. Otherwise the queries of
+/// stream `s` are read from the subdirectory `query_path/s` when it exists. Queries directly in
+/// `query_path` are used for every stream, which is not compliant because every stream then uses
+/// the same substitution parameters.
+pub fn load_query_sets(
+    query_path: &str,
+    scale: f64,
+    streams: usize,
+    generator: Option<&QueryGenerator>,
+) -> Result<Vec<Vec<String>>> {
+    let mut query_sets = vec![];
+    for stream in 0..=streams {
+        let mut queries = vec![];
+        for query in 1..=22 {
+            queries.push(match generator {
+                Some(generator) => generate_query(generator, query_path, scale, stream, query)?,
+                None => {
+                    let stream_path = Path::new(query_path).join(stream.to_string());
+                    let dir = if stream_path.is_dir() {
+                        stream_path
+                    } else {
+                        PathBuf::from(query_path)
+                    };
+                    let path = dir.join(format!("q{}.sql", query));
+                    fs::read_to_string(&path).map_err(|e| {
+                        DataFusionError::Execution(format!(
+                            "Failed to read {}: {}",
+                            path.display(),
+                            e
+                        ))
+                    })?
+                }
+            });
+        }
+        query_sets.push(queries);
+    }
+    if generator.is_none() && !Path::new(query_path).join("0").is_dir() {
+        warn!(
+            query_path,
+            "Every query stream uses the same substitution parameters"
+        );
+    }
+    Ok(query_sets)
+}
+
+/// Generate the text of a query for a stream with qgen
+fn generate_query(
+    generator: &QueryGenerator,
+    template_path: &str,
+    scale: f64,
+    stream: usize,
+    query: usize,
+) -> Result<String> {
+    // qgen reads dists.dss from the working directory and the templates from DSS_QUERY
+    let generator_path = fs::canonicalize(&generator.generator_path)?;
+    let template_path = fs::canonicalize(template_path)?;
+    let output = Command::new(generator_path.join("qgen"))
+        .current_dir(&generator_path)
+        .env("DSS_CONFIG", &generator_path)
+        .env("DSS_QUERY", &template_path)
+        .arg("-s")
+        .arg(scale.to_string())
+        .arg("-r")
+        .arg((generator.seed + stream as u64).to_string())
+        .arg(query.to_string())
+        .output()?;
+    if !output.status.success() {
+        return Err(DataFusionError::Execution(format!(
+            "qgen failed to generate query {} for stream {} with {}: {}",
+            query,
+            stream,
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    String::from_utf8(output.stdout).map_err(|e| {
+        DataFusionError::Execution(format!("qgen generated invalid query {}: {}", query, e))
+    })
+}
+
+/// Write the report as JSON
+pub fn write_report(report: &MetricReport, path: &Path) -> Result<()> {
+    let json = serde_json::to_string_pretty(report)
+        .map_err(|e| DataFusionError::Execution(format!("Failed to serialize report: {}", e)))?;
+    fs::write(path, json)?;
+    Ok(())
+}
+
+async fn join<T>(handle: tokio::task::JoinHandle<T>) -> Result<T> {
+    handle
+        .await
+        .map_err(|e| DataFusionError::Execution(format!("Task failed: {}", e)))
+}
+
+/// Power@Size is 3600 * SF divided by the geometric mean of the 22 query and 2 refresh
+/// function timings. When the longest timing is more than 1000 times the shortest, all
+/// timings are increased to at least 1/1000 of the longest one.
+fn power_at_size(scale: f64, queries: &[Timing], rf1_seconds: f64, rf2_seconds: f64) -> f64 {
+    let mut timings: Vec<f64> = queries.iter().map(|q| q.seconds).collect();
+    timings.push(rf1_seconds);
+    timings.push(rf2_seconds);
+    let max = timings.iter().cloned().fold(0.0, f64::max);
+    let floor = max / 1000.0;
+    let mean_ln = timings.iter().map(|t| t.max(floor).ln()).sum::<f64>() / timings.len() as f64;
+    3600.0 * scale / mean_ln.exp()
+}
+
+async fn run_query_stream(
+    ctx: &SessionContext,
+    lock: &RwLock<()>,
+    queries: &[String],
+    stream: usize,
+) -> Result<Vec<Timing>> {
+    let mut timings = vec![];
+    for query in QUERY_STREAMS[stream] {
+        // waiting for a refresh function to finish is not part of the query time
+        let _guard = lock.read().await;
+        let start = Instant::now();
+        execute_query(ctx, &queries[query - 1]).await?;
+        let seconds = start.elapsed().as_secs_f64();
+        info!(stream, query, seconds, "Query finished");
+        timings.push(Timing {
+            name: format!("q{}", query),
+            seconds,
+        });
+    }
+    Ok(timings)
+}
+
+/// RF1: insert the new orders and line items of an update set. The rows are written as an
+/// additional Parquet file in each table directory.
+async fn refresh_insert(
+    ctx: &SessionContext,
+    lock: &RwLock<()>,
+    data_path: &str,
+    refresh_path: &str,
+    update_set: usize,
+) -> Result<f64> {
+    let start = Instant::now();
+    let tpch = TpcH::new();
+    let file_ext = format!(".u{}", update_set);
+    for table in ["orders", "lineitem"] {
+        let schema = tpch.get_schema(table)?;
+        let options = CsvReadOptions::new()
+            .schema(&schema)
+            .delimiter(b'|')
+            .has_header(false)
+            .file_extension(&file_ext);
+        let input = format!("{}/{}.tbl{}", refresh_path, table, file_ext);
+        let temp_dir = format!("{}/refresh-temp", data_path);
+        ctx.read_csv(&input, options)
+            .await?
+            .repartition(Partitioning::RoundRobinBatch(1))?
+            .write_parquet(&temp_dir, None)
+            .await?;
+        let dest = format!(
+            "{}/{}.parquet/refresh-u{}.parquet",
+            data_path, table, update_set
+        );
+        let _guard = lock.write().await;
+        for written in find_parquet_files(Path::new(&temp_dir))? {
+            fs::rename(&written, &dest)?;
+        }
+        fs::remove_dir_all(&temp_dir)?;
+    }
+    let seconds = start.elapsed().as_secs_f64();
+    info!(update_set, seconds, "RF1 finished");
+    Ok(seconds)
+}
+
+/// RF2: delete the orders and line items whose keys are listed in the update set. Each Parquet
+/// file containing deleted rows is rewritten without them and then atomically replaced.
+async fn refresh_delete(
+    ctx: &SessionContext,
+    lock: &RwLock<()>,
+    data_path: &str,
+    refresh_path: &str,
+    update_set: usize,
+) -> Result<f64> {
+    let start = Instant::now();
+    let schema = Schema::new(vec![
+        Field::new("d_orderkey", DataType::Int64, false),
+        Field::new("ignore", DataType::Utf8, true),
+    ]);
+    let file_ext = format!(".{}", update_set);
+    let options = CsvReadOptions::new()
+        .schema(&schema)
+        .delimiter(b'|')
+        .has_header(false)
+        .file_extension(&file_ext);
+    let delete_path = format!("{}/delete{}", refresh_path, file_ext);
+    let temp_dir = format!("{}/refresh-temp", data_path);
+    // the keys are read into memory once and joined with every Parquet file
+    let deletes = ctx.read_csv(&delete_path, options).await?.cache().await?;
+
+    for (table, key) in [("orders", "o_orderkey"), ("lineitem", "l_orderkey")] {
+        let table_dir = PathBuf::from(format!("{}/{}.parquet", data_path, table));
+        for file in find_parquet_files(&table_dir)? {
+            let file_str = file.to_str().unwrap();
+            let df = ctx
+                .read_parquet(file_str, ParquetReadOptions::default())
+                .await?;
+            let matching = df
+                .clone()
+                .join(
+                    deletes.clone(),
+                    JoinType::LeftSemi,
+                    &[key],
+                    &["d_orderkey"],
+                    None,
+                )?
+                .count()
+                .await?;
+            if matching == 0 {
+                continue;
+            }
+            df.join(
+                deletes.clone(),
+                JoinType::LeftAnti,
+                &[key],
+                &["d_orderkey"],
+                None,
+            )?
+            .repartition(Partitioning::RoundRobinBatch(1))?
+            .write_parquet(&temp_dir, None)
+            .await?;
+            let _guard = lock.write().await;
+            for written in find_parquet_files(Path::new(&temp_dir))? {
+                fs::rename(&written, &file)?;
+            }
+            fs::remove_dir_all(&temp_dir)?;
+        }
+    }
+    let seconds = start.elapsed().as_secs_f64();
+    info!(update_set, seconds, "RF2 finished");
+    Ok(seconds)
+}
+
+fn find_parquet_files(dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut files = vec![];
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            files.extend(find_parquet_files(&path)?);
+        } else if path
+            .extension()
+            .map(|ext| ext == "parquet")
+            .unwrap_or(false)
+        {
+            files.push(path);
+        }
+    }
+    Ok(files)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_queries(dir: &Path, prefix: &str) -> Result<()> {
+        fs::create_dir_all(dir)?;
+        for query in 1..=22 {
+            fs::write(
+                dir.join(format!("q{}.sql", query)),
+                format!("{} q{}", prefix, query),
+            )?;
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn min_streams_by_scale() {
+        assert_eq!(2, min_streams(1.0));
+        assert_eq!(3, min_streams(10.0));
+        assert_eq!(5, min_streams(100.0));
+        assert_eq!(11, min_streams(100000.0));
+    }
+
+    #[test]
+    fn power_at_size_is_geometric_mean() {
+        let queries: Vec<Timing> = (1..=22)
+            .map(|i| Timing {
+                name: format!("q{}", i),
+                seconds: 2.0,
+            })
+            .collect();
+        assert!((power_at_size(1.0, &queries, 2.0, 2.0) - 1800.0).abs() < 1e-9);
+        // timings shorter than 1/1000 of the longest one are increased to it
+        let fast = power_at_size(1.0, &queries, 2000.0, 0.0001);
+        let floor = power_at_size(1.0, &queries, 2000.0, 2.0);
+        assert!((fast - floor).abs() < 1e-9);
+    }
+
+    #[test]
+    fn load_query_set_per_stream() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        for stream in 0..=2 {
+            write_queries(
+                &dir.path().join(stream.to_string()),
+                &format!("s{}", stream),
+            )?;
+        }
+        let query_sets = load_query_sets(dir.path().to_str().unwrap(), 1.0, 2, None)?;
+        assert_eq!(3, query_sets.len());
+        assert_eq!("s0 q1", query_sets[0][0]);
+        assert_eq!("s2 q22", query_sets[2][21]);
+        // a missing stream is an error rather than silently sharing parameters
+        assert!(load_query_sets(dir.path().to_str().unwrap(), 1.0, 3, None).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn load_shared_query_set() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        write_queries(dir.path(), "shared")?;
+        let query_sets = load_query_sets(dir.path().to_str().unwrap(), 1.0, 2, None)?;
+        assert_eq!(query_sets[0], query_sets[2]);
+        assert_eq!("shared q5", query_sets[1][4]);
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn generate_query_sets_with_qgen() -> Result<()> {
+        use std::os::unix::fs::PermissionsExt;
+
+        let generator_dir = tempfile::tempdir()?;
+        let qgen = generator_dir.path().join("qgen");
+        fs::write(&qgen, "#!/bin/sh\necho \"$DSS_QUERY/$5.sql $1 $2 $3 $4\"\n")?;
+        fs::set_permissions(&qgen, fs::Permissions::from_mode(0o755))?;
+        let template_dir = tempfile::tempdir()?;
+        let template_path = fs::canonicalize(template_dir.path())?;
+        let generator = QueryGenerator {
+            generator_path: generator_dir.path().display().to_string(),
+            seed: 100,
+        };
+        let query_sets =
+            load_query_sets(template_path.to_str().unwrap(), 10.0, 2, Some(&generator))?;
+        assert_eq!(
+            format!("{}/7.sql -s 10 -r 100\n", template_path.display()),
+            query_sets[0][6]
+        );
+        assert_eq!(
+            format!("{}/22.sql -s 10 -r 102\n", template_path.display()),
+            query_sets[2][21]
+        );
+        Ok(())
+    }
+}
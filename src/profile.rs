@@ -0,0 +1,181 @@
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::HashSet;
+use std::fmt;
+use std::str::FromStr;
+
+use datafusion::arrow::datatypes::{DataType, Field, Schema};
+use datafusion::error::DataFusionError;
+
+use crate::Tpc;
+
+/// Changes to the physical types of the built-in schemas, for engines that expect different
+/// types than the ones declared by `Tpc::get_schema`.
+///
+/// A profile is parsed from a comma-separated list of the following names:
+///
+/// - `default`: the built-in schemas, which use Int64 keys and Decimal128(11, 2) for TPC-H and
+///   Int32 keys and Decimal128(7, 2) for TPC-DS
+/// - `int32-keys` or `int64-keys`: the same integer type for the key columns of both benchmarks,
+///   which are the foreign key columns, the primary key columns that foreign keys refer to and
+///   single-column primary keys. Other columns of a composite primary key, such as
+///   `l_linenumber`, keep their type. Note that TPC-H order keys do not fit in an Int32 at scale
+///   factor 1000 and above.
+/// - `float-decimals`: Float64 instead of decimals
+/// - `string-dates`: Utf8 instead of Date32, with the dates in `YYYY-MM-DD` form
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SchemaProfile {
+    key_type: Option<DataType>,
+    float_decimals: bool,
+    string_dates: bool,
+}
+
+impl SchemaProfile {
+    /// Schema of a table with the changes of this profile applied
+    pub fn get_schema(&self, benchmark: &dyn Tpc, table: &str) -> std::io::Result<Schema> {
+        let schema = benchmark.get_schema(table)?;
+        if *self == Self::default() {
+            return Ok(schema);
+        }
+        let mut keys: HashSet<String> = HashSet::new();
+        let primary_key = benchmark.get_primary_key(table)?;
+        if primary_key.len() == 1 {
+            keys.insert(primary_key[0].to_owned());
+        }
+        for foreign_key in benchmark.get_foreign_keys(table)? {
+            keys.extend(foreign_key.columns);
+        }
+        for other in benchmark.get_table_names() {
+            for foreign_key in benchmark.get_foreign_keys(other)? {
+                if foreign_key.referenced_table == table {
+                    keys.extend(foreign_key.referenced_columns);
+                }
+            }
+        }
+        let fields = schema
+            .fields()
+            .iter()
+            .map(|f| {
+                let data_type = self.data_type(f.data_type(), keys.contains(f.name()));
+                Field::new(f.name(), data_type, f.is_nullable())
+            })
+            .collect::<Vec<_>>();
+        Ok(Schema::new(fields))
+    }
+
+    fn data_type(&self, data_type: &DataType, is_key: bool) -> DataType {
+        match data_type {
+            DataType::Int32 | DataType::Int64 if is_key => {
+                self.key_type.clone().unwrap_or_else(|| data_type.clone())
+            }
+            DataType::Decimal128(_, _) if self.float_decimals => DataType::Float64,
+            DataType::Date32 if self.string_dates => DataType::Utf8,
+            other => other.clone(),
+        }
+    }
+}
+
+impl FromStr for SchemaProfile {
+    type Err = DataFusionError;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let mut profile = Self::default();
+        for name in s.split(',').map(|name| name.trim()) {
+            match name {
+                "default" => {}
+                "int32-keys" => profile.key_type = Some(DataType::Int32),
+                "int64-keys" => profile.key_type = Some(DataType::Int64),
+                "float-decimals" => profile.float_decimals = true,
+                "string-dates" => profile.string_dates = true,
+                other => {
+                    return Err(DataFusionError::NotImplemented(format!(
+                        "Invalid schema profile: {}",
+                        other
+                    )))
+                }
+            }
+        }
+        Ok(profile)
+    }
+}
+
+impl fmt::Display for SchemaProfile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut names = vec![];
+        match self.key_type {
+            Some(DataType::Int32) => names.push("int32-keys"),
+            Some(_) => names.push("int64-keys"),
+            None => {}
+        }
+        if self.float_decimals {
+            names.push("float-decimals");
+        }
+        if self.string_dates {
+            names.push("string-dates");
+        }
+        if names.is_empty() {
+            names.push("default");
+        }
+        write!(f, "{}", names.join(","))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tpcds::TpcDs;
+    use crate::tpch::TpcH;
+
+    fn data_type(schema: &Schema, column: &str) -> DataType {
+        schema.field_with_name(column).unwrap().data_type().clone()
+    }
+
+    #[test]
+    fn key_profiles_only_change_key_columns() -> std::io::Result<()> {
+        let profile: SchemaProfile = "int64-keys".parse().unwrap();
+        let lineitem = profile.get_schema(&TpcH::new(), "lineitem")?;
+        assert_eq!(DataType::Int64, data_type(&lineitem, "l_orderkey"));
+        assert_eq!(DataType::Int32, data_type(&lineitem, "l_linenumber"));
+
+        let store_sales = profile.get_schema(&TpcDs::new(), "store_sales")?;
+        assert_eq!(DataType::Int64, data_type(&store_sales, "ss_item_sk"));
+        assert_eq!(DataType::Int64, data_type(&store_sales, "ss_sold_date_sk"));
+        // the returns refer to the ticket number of the sale
+        assert_eq!(DataType::Int64, data_type(&store_sales, "ss_ticket_number"));
+        assert_eq!(DataType::Int32, data_type(&store_sales, "ss_quantity"));
+
+        let profile: SchemaProfile = "int32-keys".parse().unwrap();
+        let nation = profile.get_schema(&TpcH::new(), "nation")?;
+        assert_eq!(DataType::Int32, data_type(&nation, "n_nationkey"));
+        assert_eq!(DataType::Int32, data_type(&nation, "n_regionkey"));
+        Ok(())
+    }
+
+    #[test]
+    fn type_profiles() -> std::io::Result<()> {
+        let profile: SchemaProfile = "float-decimals, string-dates".parse().unwrap();
+        let lineitem = profile.get_schema(&TpcH::new(), "lineitem")?;
+        assert_eq!(DataType::Float64, data_type(&lineitem, "l_extendedprice"));
+        assert_eq!(DataType::Utf8, data_type(&lineitem, "l_shipdate"));
+        assert_eq!(DataType::Int64, data_type(&lineitem, "l_orderkey"));
+        Ok(())
+    }
+
+    #[test]
+    fn parse_and_display() {
+        let profile: SchemaProfile = "string-dates,int32-keys".parse().unwrap();
+        assert_eq!("int32-keys,string-dates", profile.to_string());
+        assert_eq!("default", SchemaProfile::default().to_string());
+        assert!("int16-keys".parse::<SchemaProfile>().is_err());
+    }
+}
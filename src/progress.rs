@@ -0,0 +1,487 @@
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Progress of generating and converting data sets. Events are reported to a callback along
+//! with the overall status, which includes an estimate of the time remaining.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use tracing::info;
+
+/// How often the watcher checks the size of the output files
+const WATCH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// How often the terminal display prints the status between other events
+const DISPLAY_INTERVAL: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ProgressEvent {
+    /// Generation started with this number of generator processes or threads. The size of
+    /// the output is estimated from the scale factor when the output is not compressed.
+    GenerationStarted {
+        children: usize,
+        expected_bytes: Option<u64>,
+    },
+    /// A generator process or thread finished
+    ChildFinished { child: usize },
+    /// The files of a table written so far add up to `bytes`
+    BytesWritten { table: String, bytes: u64 },
+    /// Conversion started with this number of input files
+    ConversionStarted { files: usize, input_bytes: u64 },
+    /// The input file `input` of a table, of `input_bytes` bytes, was converted to
+    /// `output_bytes` bytes of Parquet, bringing the number of rows converted for the table to
+    /// `rows`
+    FileConverted {
+        table: String,
+        input: String,
+        input_bytes: u64,
+        output_bytes: u64,
+        rows: u64,
+    },
+    /// Generation or conversion finished
+    Finished,
+}
+
+/// Status of the current generation or conversion, updated by each event
+#[derive(Debug, Clone, Default)]
+pub struct ProgressStatus {
+    /// Number of generator processes or threads, or input files when converting
+    pub children: usize,
+    pub completed_children: usize,
+    pub expected_bytes: Option<u64>,
+    pub bytes_written: BTreeMap<String, u64>,
+    pub input_bytes: u64,
+    pub converted_bytes: u64,
+    pub rows_converted: BTreeMap<String, u64>,
+    pub elapsed: Duration,
+    /// Estimated time remaining
+    pub eta: Option<Duration>,
+    pub finished: bool,
+}
+
+impl ProgressStatus {
+    pub fn total_bytes_written(&self) -> u64 {
+        self.bytes_written.values().sum()
+    }
+
+    pub fn total_rows_converted(&self) -> u64 {
+        self.rows_converted.values().sum()
+    }
+
+    /// Estimated fraction of the work that is done. Generation is measured by the larger of
+    /// the fraction of children that finished and of the expected bytes that were written,
+    /// and conversion by the fraction of the input bytes that were converted.
+    pub fn fraction_done(&self) -> Option<f64> {
+        if self.finished {
+            return Some(1.0);
+        }
+        let mut fraction = None;
+        if self.children > 0 {
+            fraction = Some(self.completed_children as f64 / self.children as f64);
+        }
+        if self.input_bytes > 0 {
+            fraction = Some(self.converted_bytes as f64 / self.input_bytes as f64);
+        } else if let Some(expected_bytes) = self.expected_bytes.filter(|b| *b > 0) {
+            let written = self.total_bytes_written() as f64 / expected_bytes as f64;
+            fraction = Some(fraction.unwrap_or_default().max(written));
+        }
+        // the estimate can be exceeded before the work is done, at which point the time
+        // remaining is unknown
+        fraction.filter(|f| *f < 1.0)
+    }
+}
+
+type Callback = dyn Fn(&ProgressEvent, &ProgressStatus) + Send + Sync;
+
+#[derive(Default)]
+struct State {
+    status: ProgressStatus,
+    start: Option<Instant>,
+}
+
+/// Receives the progress of generating or converting a data set. Clones report to the same
+/// status, so a clone can be passed to each thread.
+#[derive(Clone, Default)]
+pub struct Progress {
+    state: Arc<Mutex<State>>,
+    callback: Option<Arc<Callback>>,
+}
+
+impl Progress {
+    /// Report progress to a callback, which is called with each event and the updated status
+    pub fn new(callback: impl Fn(&ProgressEvent, &ProgressStatus) + Send + Sync + 'static) -> Self {
+        Self {
+            state: Arc::new(Mutex::new(State::default())),
+            callback: Some(Arc::new(callback)),
+        }
+    }
+
+    /// Also report progress to `callback`, after the callbacks this already reports to
+    pub fn with_callback(
+        &self,
+        callback: impl Fn(&ProgressEvent, &ProgressStatus) + Send + Sync + 'static,
+    ) -> Self {
+        let previous = self.callback.clone();
+        Self {
+            state: self.state.clone(),
+            callback: Some(Arc::new(move |event, status| {
+                if let Some(previous) = &previous {
+                    previous(event, status);
+                }
+                callback(event, status);
+            })),
+        }
+    }
+
+    /// Ignore progress
+    pub fn none() -> Self {
+        Self::default()
+    }
+
+    /// Report progress as a stream of events with the status after each event
+    pub fn channel() -> (Self, Receiver<(ProgressEvent, ProgressStatus)>) {
+        let (tx, rx) = mpsc::channel();
+        let tx = Mutex::new(tx);
+        let progress = Self::new(move |event, status| {
+            // the receiver may have been dropped, in which case progress is ignored
+            let _ = tx.lock().unwrap().send((event.clone(), status.clone()));
+        });
+        (progress, rx)
+    }
+
+    /// Log the status at info level when a child finishes or a file is converted, and
+    /// otherwise at most every 10 seconds
+    pub fn terminal() -> Self {
+        let last_display = Mutex::new(None::<Instant>);
+        Self::new(move |event, status| {
+            let mut last_display = last_display.lock().unwrap();
+            let display = match event {
+                ProgressEvent::BytesWritten { .. } => {
+                    last_display.is_none_or(|t| t.elapsed() >= DISPLAY_INTERVAL)
+                }
+                ProgressEvent::GenerationStarted { .. }
+                | ProgressEvent::ConversionStarted { .. } => false,
+                _ => true,
+            };
+            if display {
+                info!(
+                    completed = status.completed_children,
+                    total = status.children,
+                    bytes = status.total_bytes_written(),
+                    rows = status.total_rows_converted(),
+                    eta_secs = status.eta.map(|eta| eta.as_secs()),
+                    "{}",
+                    format_status(status)
+                );
+                *last_display = Some(Instant::now());
+            }
+        })
+    }
+
+    pub fn report(&self, event: ProgressEvent) {
+        let status = {
+            let mut state = self.state.lock().unwrap();
+            let now = Instant::now();
+            match &event {
+                ProgressEvent::GenerationStarted {
+                    children,
+                    expected_bytes,
+                } => {
+                    state.start = Some(now);
+                    state.status = ProgressStatus {
+                        children: *children,
+                        expected_bytes: *expected_bytes,
+                        ..Default::default()
+                    };
+                }
+                ProgressEvent::ConversionStarted { files, input_bytes } => {
+                    state.start = Some(now);
+                    state.status = ProgressStatus {
+                        children: *files,
+                        input_bytes: *input_bytes,
+                        ..Default::default()
+                    };
+                }
+                ProgressEvent::ChildFinished { .. } => state.status.completed_children += 1,
+                ProgressEvent::BytesWritten { table, bytes } => {
+                    state.status.bytes_written.insert(table.clone(), *bytes);
+                }
+                ProgressEvent::FileConverted {
+                    table,
+                    input_bytes,
+                    rows,
+                    ..
+                } => {
+                    state.status.completed_children += 1;
+                    state.status.converted_bytes += input_bytes;
+                    state.status.rows_converted.insert(table.clone(), *rows);
+                }
+                ProgressEvent::Finished => state.status.finished = true,
+            }
+            let elapsed = now.duration_since(*state.start.get_or_insert(now));
+            state.status.elapsed = elapsed;
+            state.status.eta = match state.status.fraction_done() {
+                Some(f) if f > 0.0 => Some(elapsed.mul_f64((1.0 - f) / f)),
+                _ => None,
+            };
+            state.status.clone()
+        };
+        if let Some(callback) = &self.callback {
+            callback(&event, &status);
+        }
+    }
+
+    /// Report the size of the files of each table under `path` every second until the watcher
+    /// is dropped. Files in the working directories of the generators are included.
+    pub fn watch(&self, path: &str, tables: &[&str]) -> Watcher {
+        let stop = Arc::new(AtomicBool::new(false));
+        let handle = if self.callback.is_some() {
+            let progress = self.clone();
+            let path = PathBuf::from(path);
+            let tables: Vec<String> = tables.iter().map(|t| t.to_string()).collect();
+            let stop = stop.clone();
+            Some(thread::spawn(move || {
+                let mut sizes = BTreeMap::new();
+                loop {
+                    // check once more after being stopped, to report the final sizes
+                    let stopped = stop.load(Ordering::Relaxed);
+                    for (table, bytes) in table_sizes(&path, &tables) {
+                        if sizes.get(&table) != Some(&bytes) {
+                            sizes.insert(table.clone(), bytes);
+                            progress.report(ProgressEvent::BytesWritten { table, bytes });
+                        }
+                    }
+                    if stopped {
+                        break;
+                    }
+                    thread::park_timeout(WATCH_INTERVAL);
+                }
+            }))
+        } else {
+            None
+        };
+        Watcher { stop, handle }
+    }
+}
+
+/// Watches the size of output files, see [`Progress::watch`]
+pub struct Watcher {
+    stop: Arc<AtomicBool>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl Drop for Watcher {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(handle) = self.handle.take() {
+            handle.thread().unpark();
+            let _ = handle.join();
+        }
+    }
+}
+
+/// Total size of the files of each table under `path`
+fn table_sizes(path: &Path, tables: &[String]) -> BTreeMap<String, u64> {
+    let mut sizes = BTreeMap::new();
+    add_sizes(path, tables, None, &mut sizes);
+    sizes
+}
+
+fn add_sizes(
+    path: &Path,
+    tables: &[String],
+    table: Option<&str>,
+    sizes: &mut BTreeMap<String, u64>,
+) {
+    // files can be moved or removed while the directory is read, so errors are ignored
+    let entries = match fs::read_dir(path) {
+        Ok(entries) => entries,
+        Err(_) => return,
+    };
+    for entry in entries.flatten() {
+        let name = entry.file_name().to_string_lossy().to_string();
+        // files in `<table>.tbl/` directories belong to the table, while the working
+        // directories of the generators contain files such as `lineitem.tbl.1` and
+        // `store_sales_1_4.dat`
+        let table = table.or_else(|| table_of(&name, tables));
+        let metadata = match entry.metadata() {
+            Ok(metadata) => metadata,
+            Err(_) => continue,
+        };
+        if metadata.is_dir() {
+            if table.is_some() || name.starts_with(".generate-") {
+                add_sizes(&entry.path(), tables, table, sizes);
+            }
+        } else if let Some(table) = table {
+            *sizes.entry(table.to_owned()).or_default() += metadata.len();
+        }
+    }
+}
+
+/// The table that a file or directory name belongs to, which is the longest table name that
+/// the name starts with followed by `.` or `_`
+fn table_of<'a>(name: &str, tables: &'a [String]) -> Option<&'a str> {
+    tables
+        .iter()
+        .filter(|table| {
+            name.strip_prefix(table.as_str())
+                .is_some_and(|rest| rest.starts_with('.') || rest.starts_with('_'))
+        })
+        .max_by_key(|table| table.len())
+        .map(|table| table.as_str())
+}
+
+fn format_status(status: &ProgressStatus) -> String {
+    let mut text = format!("[{}]", format_duration(status.elapsed));
+    if status.input_bytes > 0 {
+        text += &format!(
+            " {} of {} files converted, {} rows",
+            status.completed_children,
+            status.children,
+            status.total_rows_converted()
+        );
+    } else {
+        text += &format!(
+            " {} of {} partitions generated, {} written",
+            status.completed_children,
+            status.children,
+            format_bytes(status.total_bytes_written())
+        );
+    }
+    if let Some(fraction) = status.fraction_done() {
+        text += &format!(" ({:.1}%)", fraction * 100.0);
+    }
+    match status.eta {
+        _ if status.finished => text += ", done",
+        Some(eta) => text += &format!(", ETA {}", format_duration(eta)),
+        None => {}
+    }
+    text
+}
+
+fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KiB", "MiB", "GiB", "TiB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", size, UNITS[unit])
+}
+
+fn format_duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    format!("{}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn status_follows_events() {
+        let (progress, events) = Progress::channel();
+        progress.report(ProgressEvent::GenerationStarted {
+            children: 4,
+            expected_bytes: Some(1000),
+        });
+        progress.report(ProgressEvent::ChildFinished { child: 1 });
+        progress.report(ProgressEvent::BytesWritten {
+            table: "lineitem".to_owned(),
+            bytes: 500,
+        });
+        progress.report(ProgressEvent::BytesWritten {
+            table: "lineitem".to_owned(),
+            bytes: 600,
+        });
+        progress.report(ProgressEvent::Finished);
+        drop(progress);
+
+        let statuses: Vec<ProgressStatus> = events.iter().map(|(_, status)| status).collect();
+        assert_eq!(5, statuses.len());
+        // a quarter of the children finished but half of the expected bytes were written
+        assert_eq!(Some(0.25), statuses[1].fraction_done());
+        assert_eq!(Some(0.5), statuses[2].fraction_done());
+        // sizes replace the previous size of a table rather than adding to it
+        assert_eq!(600, statuses[3].total_bytes_written());
+        assert_eq!(Some(1.0), statuses[4].fraction_done());
+        assert!(statuses[4].finished);
+    }
+
+    #[test]
+    fn conversion_is_measured_by_input_bytes() {
+        let (progress, events) = Progress::channel();
+        progress.report(ProgressEvent::ConversionStarted {
+            files: 2,
+            input_bytes: 400,
+        });
+        progress.report(ProgressEvent::FileConverted {
+            table: "nation".to_owned(),
+            input: "part-0.tbl".to_owned(),
+            input_bytes: 100,
+            output_bytes: 50,
+            rows: 25,
+        });
+        drop(progress);
+
+        let (_, status) = events.iter().last().unwrap();
+        assert_eq!(1, status.completed_children);
+        assert_eq!(25, status.total_rows_converted());
+        assert_eq!(Some(0.25), status.fraction_done());
+        assert!(status.eta.is_some());
+    }
+
+    #[test]
+    fn table_of_prefers_longest_name() {
+        let tables: Vec<String> = ["store", "store_sales", "store_returns"]
+            .iter()
+            .map(|t| t.to_string())
+            .collect();
+        assert_eq!(
+            Some("store_sales"),
+            table_of("store_sales_1_4.dat", &tables)
+        );
+        assert_eq!(Some("store"), table_of("store.dat", &tables));
+        assert_eq!(Some("store"), table_of("store_1_4.dat", &tables));
+        assert_eq!(None, table_of("stores.dat", &tables));
+    }
+
+    #[test]
+    fn table_sizes_include_work_dirs() -> std::io::Result<()> {
+        let dir = tempfile::tempdir()?;
+        fs::create_dir(dir.path().join("nation.tbl"))?;
+        fs::write(dir.path().join("nation.tbl").join("part-0.tbl"), [0; 10])?;
+        fs::create_dir(dir.path().join(".generate-1"))?;
+        fs::write(dir.path().join(".generate-1").join("nation.tbl.1"), [0; 5])?;
+        fs::write(dir.path().join("manifest.json"), [0; 100])?;
+
+        let sizes = table_sizes(dir.path(), &["nation".to_owned(), "region".to_owned()]);
+        assert_eq!(BTreeMap::from([("nation".to_owned(), 15)]), sizes);
+        Ok(())
+    }
+
+    #[test]
+    fn format_sizes_and_durations() {
+        assert_eq!("512.0 B", format_bytes(512));
+        assert_eq!("1.5 KiB", format_bytes(1536));
+        assert_eq!("2.0 GiB", format_bytes(2 << 30));
+        assert_eq!("1:01:05", format_duration(Duration::from_secs(3665)));
+    }
+}
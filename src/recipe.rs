@@ -0,0 +1,377 @@
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Recipes describe data sets in a TOML file, so that the variants of the data sets used for
+//! benchmarking can be reviewed and rebuilt with the `build` subcommand:
+//!
+//! ```toml
+//! output = "/data"
+//!
+//! [[dataset]]
+//! name = "tpch-sf100"
+//! benchmark = "tpch"
+//! scale = 100
+//! partitions = 48
+//! generator_path = "/opt/tpch-dbgen"
+//!
+//! [[dataset]]
+//! name = "tpcds-sf10-lz4"
+//! benchmark = "tpcds"
+//! scale = 10
+//! partitions = 8
+//! generator_path = "/opt/tpcds-kit/tools"
+//! compression = "lz4"
+//! schema = "int64-keys,string-dates"
+//! ```
+
+use std::collections::HashSet;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use datafusion::error::{DataFusionError, Result};
+use serde::de::{self, Deserializer};
+use serde::Deserialize;
+
+use crate::manifest::Manifest;
+use crate::profile::SchemaProfile;
+use crate::Format;
+
+const BENCHMARKS: [&str; 7] = ["tpcds", "tpc-ds", "tpch", "tpc-h", "jcch", "jcc-h", "ssb"];
+
+const COMPRESSIONS: [&str; 3] = ["snappy", "lz4", "none"];
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Recipe {
+    /// Directory that each data set is built in a subdirectory of. Relative paths in the recipe
+    /// are relative to the directory of the recipe file.
+    pub output: PathBuf,
+    #[serde(default, rename = "dataset")]
+    pub datasets: Vec<DatasetRecipe>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DatasetRecipe {
+    /// Name of the subdirectory of the output that the data set is built in
+    pub name: String,
+    pub benchmark: String,
+    pub scale: f64,
+    #[serde(default = "default_partitions")]
+    pub partitions: usize,
+    /// Path to the generator. TPC-H data is generated natively when this is omitted.
+    pub generator_path: Option<PathBuf>,
+    pub seed: Option<u64>,
+    #[serde(default)]
+    pub refresh_sets: usize,
+    /// Format of the data set. Parquet data sets are generated as raw files first, which are
+    /// kept next to the Parquet files.
+    #[serde(default = "default_format", deserialize_with = "parse")]
+    pub format: Format,
+    /// Compression codec of the Parquet files
+    #[serde(default = "default_compression")]
+    pub compression: String,
+    #[serde(default, deserialize_with = "parse")]
+    pub schema: SchemaProfile,
+}
+
+/// Benchmark names without the aliases with a hyphen
+fn canonical_benchmark(name: &str) -> String {
+    name.replace('-', "")
+}
+
+fn default_partitions() -> usize {
+    1
+}
+
+fn default_format() -> Format {
+    Format::Parquet
+}
+
+fn default_compression() -> String {
+    "snappy".to_owned()
+}
+
+/// Deserialize a value from a string, such as `"int32-keys,string-dates"` for a schema profile
+fn parse<'de, D, T>(deserializer: D) -> std::result::Result<T, D::Error>
+where
+    D: Deserializer<'de>,
+    T: FromStr,
+    T::Err: fmt::Display,
+{
+    let s = String::deserialize(deserializer)?;
+    s.parse().map_err(de::Error::custom)
+}
+
+impl Recipe {
+    /// Read and validate a recipe, resolving relative paths against the directory of the file
+    pub fn read(path: &Path) -> Result<Self> {
+        let toml = fs::read_to_string(path)?;
+        let mut recipe: Recipe = toml::from_str(&toml).map_err(|e| {
+            DataFusionError::Execution(format!("Failed to parse {}: {}", path.display(), e))
+        })?;
+
+        let base_path = path.parent().unwrap_or_else(|| Path::new(""));
+        recipe.output = base_path.join(&recipe.output);
+        for dataset in &mut recipe.datasets {
+            if let Some(generator_path) = &dataset.generator_path {
+                dataset.generator_path = Some(base_path.join(generator_path));
+            }
+        }
+
+        recipe.validate().map_err(|e| {
+            DataFusionError::Execution(format!("Invalid recipe {}: {}", path.display(), e))
+        })?;
+        Ok(recipe)
+    }
+
+    fn validate(&self) -> std::result::Result<(), String> {
+        let mut names = HashSet::new();
+        for dataset in &self.datasets {
+            let name = &dataset.name;
+            if name.is_empty() || name.starts_with('.') || name.contains(['/', '\\']) {
+                return Err(format!("invalid dataset name '{}'", name));
+            }
+            if !names.insert(name) {
+                return Err(format!("dataset '{}' is defined more than once", name));
+            }
+            if !BENCHMARKS.contains(&dataset.benchmark.as_str()) {
+                return Err(format!(
+                    "dataset '{}' has invalid benchmark '{}'",
+                    name, dataset.benchmark
+                ));
+            }
+            if !COMPRESSIONS.contains(&dataset.compression.as_str()) {
+                return Err(format!(
+                    "dataset '{}' has invalid compression '{}', expected one of {}",
+                    name,
+                    dataset.compression,
+                    COMPRESSIONS.join(", ")
+                ));
+            }
+            if dataset.scale.is_nan() || dataset.scale <= 0.0 || dataset.partitions == 0 {
+                return Err(format!(
+                    "dataset '{}' must have a positive scale and number of partitions",
+                    name
+                ));
+            }
+            // only TPC-H can be generated without an external generator, and the seed is
+            // passed to the native generator or to dsdgen
+            let benchmark = canonical_benchmark(&dataset.benchmark);
+            match &dataset.generator_path {
+                None if benchmark != "tpch" => {
+                    return Err(format!(
+                        "dataset '{}' needs a generator_path to generate {}",
+                        name, dataset.benchmark
+                    ))
+                }
+                None if dataset.refresh_sets > 0 => {
+                    return Err(format!(
+                        "dataset '{}' needs a generator_path to generate refresh data",
+                        name
+                    ))
+                }
+                Some(_) if dataset.seed.is_some() && benchmark != "tpcds" => {
+                    return Err(format!(
+                        "dataset '{}' cannot have a seed, which is only supported for tpcds or \
+                         for tpch without a generator_path",
+                        name
+                    ))
+                }
+                _ => {}
+            }
+            if dataset.refresh_sets > 0 && benchmark != "tpch" {
+                return Err(format!(
+                    "dataset '{}' cannot have refresh sets, which are only supported for tpch",
+                    name
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// The directory that a data set is built in
+    pub fn dataset_path(&self, dataset: &DatasetRecipe) -> PathBuf {
+        self.output.join(&dataset.name)
+    }
+
+    /// The directory of the generated raw files of a data set
+    pub fn raw_path(&self, dataset: &DatasetRecipe) -> PathBuf {
+        self.dataset_path(dataset).join("raw")
+    }
+
+    /// Check that an existing data set was built with the benchmark, scale factor and number of
+    /// partitions of its recipe, so that a changed recipe is not silently skipped
+    pub fn check_manifest(&self, dataset: &DatasetRecipe, manifest: &Manifest) -> Result<()> {
+        let mut differences = vec![];
+        if canonical_benchmark(&manifest.benchmark) != canonical_benchmark(&dataset.benchmark) {
+            differences.push(format!(
+                "benchmark {} instead of {}",
+                manifest.benchmark, dataset.benchmark
+            ));
+        }
+        if manifest.scale != Some(dataset.scale) {
+            differences.push(format!(
+                "scale {} instead of {}",
+                manifest.scale.map(|s| s.to_string()).unwrap_or_default(),
+                dataset.scale
+            ));
+        }
+        if manifest.partitions != Some(dataset.partitions) {
+            differences.push(format!(
+                "{} partitions instead of {}",
+                manifest
+                    .partitions
+                    .map(|p| p.to_string())
+                    .unwrap_or_default(),
+                dataset.partitions
+            ));
+        }
+        if differences.is_empty() {
+            return Ok(());
+        }
+        Err(DataFusionError::Execution(format!(
+            "Dataset {} was built with {}, remove it to build it again",
+            dataset.name,
+            differences.join(", ")
+        )))
+    }
+
+    /// The directory of the Parquet files of a data set, if it is converted to Parquet
+    pub fn parquet_path(&self, dataset: &DatasetRecipe) -> Option<PathBuf> {
+        match dataset.format {
+            Format::Parquet => Some(self.dataset_path(dataset).join("parquet")),
+            Format::Raw => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_recipe(toml: &str) -> Recipe {
+        toml::from_str(toml).unwrap()
+    }
+
+    fn validate(dataset: &str) -> std::result::Result<(), String> {
+        parse_recipe(&format!("output = \"data\"\n[[dataset]]\n{}", dataset)).validate()
+    }
+
+    fn manifest(benchmark: &str, scale: f64, partitions: usize) -> Manifest {
+        Manifest {
+            benchmark: benchmark.to_owned(),
+            scale: Some(scale),
+            partitions: Some(partitions),
+            generator: None,
+            seed: None,
+            tpctools_version: String::new(),
+            format: "raw".to_owned(),
+            compression: None,
+            schema_profile: None,
+            source_path: None,
+            created_time: 0,
+            elapsed_secs: 0.0,
+            tables: vec![],
+        }
+    }
+
+    #[test]
+    fn read_recipe() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("recipe.toml");
+        fs::write(
+            &path,
+            "output = \"data\"\n\
+             [[dataset]]\n\
+             name = \"tpcds-sf10\"\n\
+             benchmark = \"tpcds\"\n\
+             scale = 10\n\
+             generator_path = \"tools\"\n\
+             schema = \"int64-keys\"\n",
+        )?;
+        let recipe = Recipe::read(&path)?;
+        let dataset = &recipe.datasets[0];
+        assert_eq!(
+            dir.path().join("data/tpcds-sf10/raw"),
+            recipe.raw_path(dataset)
+        );
+        assert_eq!(Some(dir.path().join("tools")), dataset.generator_path);
+        assert_eq!(1, dataset.partitions);
+        assert_eq!("snappy", dataset.compression);
+        assert_eq!("int64-keys".parse::<SchemaProfile>()?, dataset.schema);
+        Ok(())
+    }
+
+    #[test]
+    fn validate_datasets() {
+        assert!(validate("name = \"a\"\nbenchmark = \"tpch\"\nscale = 1\nseed = 3").is_ok());
+        assert!(validate("name = \"../a\"\nbenchmark = \"tpch\"\nscale = 1").is_err());
+        assert!(validate("name = \"a\"\nbenchmark = \"tpcx\"\nscale = 1").is_err());
+        assert!(validate("name = \"a\"\nbenchmark = \"tpch\"\nscale = 0").is_err());
+        assert!(validate("name = \"a\"\nbenchmark = \"tpch\"\nscale = nan").is_err());
+        assert!(validate("name = \"a\"\nbenchmark = \"tpch\"\nscale = 1\npartitions = 0").is_err());
+        let duplicate = "output = \"data\"\n\
+                         [[dataset]]\nname = \"a\"\nbenchmark = \"tpch\"\nscale = 1\n\
+                         [[dataset]]\nname = \"a\"\nbenchmark = \"tpch\"\nscale = 2\n";
+        assert!(parse_recipe(duplicate).validate().is_err());
+    }
+
+    #[test]
+    fn validate_generator_options() {
+        // only TPC-H has a native generator
+        assert!(validate("name = \"a\"\nbenchmark = \"tpcds\"\nscale = 1").is_err());
+        assert!(validate("name = \"a\"\nbenchmark = \"ssb\"\nscale = 1").is_err());
+        assert!(validate(
+            "name = \"a\"\nbenchmark = \"tpcds\"\nscale = 1\ngenerator_path = \"g\"\nseed = 1"
+        )
+        .is_ok());
+        // dbgen and the SSB generator are run with their default seed
+        assert!(validate(
+            "name = \"a\"\nbenchmark = \"tpch\"\nscale = 1\ngenerator_path = \"g\"\nseed = 1"
+        )
+        .is_err());
+        assert!(validate(
+            "name = \"a\"\nbenchmark = \"ssb\"\nscale = 1\ngenerator_path = \"g\"\nseed = 1"
+        )
+        .is_err());
+        assert!(
+            validate("name = \"a\"\nbenchmark = \"tpch\"\nscale = 1\nrefresh_sets = 2").is_err()
+        );
+    }
+
+    #[test]
+    fn check_existing_manifest() {
+        let recipe = parse_recipe(
+            "output = \"data\"\n\
+             [[dataset]]\nname = \"a\"\nbenchmark = \"tpc-h\"\nscale = 10\npartitions = 4\n",
+        );
+        let dataset = &recipe.datasets[0];
+        assert!(recipe
+            .check_manifest(dataset, &manifest("tpch", 10.0, 4))
+            .is_ok());
+        let err = recipe
+            .check_manifest(dataset, &manifest("tpcds", 1.0, 4))
+            .unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("was built with benchmark tpcds instead of tpc-h, scale 1 instead of 10"),
+            "{}",
+            err
+        );
+        assert!(recipe
+            .check_manifest(dataset, &manifest("tpch", 10.0, 8))
+            .is_err());
+    }
+}
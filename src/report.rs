@@ -0,0 +1,268 @@
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Timing report of generating or converting a data set, for tracking the performance of the
+//! generators and the conversion over time. The timings are recorded from the progress events
+//! and the sizes and row counts of the output are taken from its manifest.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use datafusion::error::{DataFusionError, Result};
+use serde::Serialize;
+
+use crate::manifest::Manifest;
+use crate::progress::{Progress, ProgressEvent, ProgressStatus};
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TimingReport {
+    /// The command that produced the data set (generate or convert)
+    pub command: String,
+    pub benchmark: String,
+    pub scale: Option<f64>,
+    pub partitions: Option<usize>,
+    pub format: String,
+    pub tpctools_version: String,
+    /// Seconds since the Unix epoch
+    pub created_time: u64,
+    pub elapsed_secs: f64,
+    /// Number of rows, when it is known for all tables
+    pub rows: Option<u64>,
+    /// Size of the converted files, or zero when generating
+    pub input_bytes: u64,
+    pub output_bytes: u64,
+    /// Input bytes divided by output bytes, when converting
+    pub compression_ratio: Option<f64>,
+    /// Peak resident memory of the tpctools process
+    pub peak_memory_bytes: Option<u64>,
+    /// Peak resident memory of the largest generator process
+    pub peak_child_memory_bytes: Option<u64>,
+    /// Time taken by each generator process or thread, which run in parallel
+    pub partition_timings: Vec<PartitionTiming>,
+    pub tables: Vec<TableTiming>,
+    /// Time taken to convert each input file, in the order they were converted
+    pub files: Vec<FileTiming>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PartitionTiming {
+    pub partition: usize,
+    pub elapsed_secs: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TableTiming {
+    pub table: String,
+    pub files: usize,
+    pub rows: Option<u64>,
+    pub input_bytes: u64,
+    pub output_bytes: u64,
+    pub compression_ratio: Option<f64>,
+    /// Time taken to convert the files of the table. Tables are generated together, so this is
+    /// only known when converting.
+    pub elapsed_secs: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FileTiming {
+    pub table: String,
+    pub input: String,
+    pub rows: u64,
+    pub input_bytes: u64,
+    pub output_bytes: u64,
+    pub compression_ratio: Option<f64>,
+    pub elapsed_secs: f64,
+}
+
+#[derive(Default)]
+struct RecorderState {
+    partition_timings: Vec<PartitionTiming>,
+    files: Vec<FileTiming>,
+    /// Rows converted so far for each table
+    table_rows: BTreeMap<String, u64>,
+    /// Elapsed time of the previous event, which is when the current file started converting
+    last_elapsed: Duration,
+    elapsed: Duration,
+}
+
+impl RecorderState {
+    fn update(&mut self, event: &ProgressEvent, status: &ProgressStatus) {
+        match event {
+            ProgressEvent::ChildFinished { child } => {
+                self.partition_timings.push(PartitionTiming {
+                    partition: *child,
+                    elapsed_secs: status.elapsed.as_secs_f64(),
+                });
+            }
+            ProgressEvent::FileConverted {
+                table,
+                input,
+                input_bytes,
+                output_bytes,
+                rows,
+            } => {
+                let previous_rows = self.table_rows.insert(table.clone(), *rows);
+                self.files.push(FileTiming {
+                    table: table.clone(),
+                    input: input.clone(),
+                    rows: rows - previous_rows.unwrap_or_default(),
+                    input_bytes: *input_bytes,
+                    output_bytes: *output_bytes,
+                    compression_ratio: ratio(*input_bytes, *output_bytes),
+                    elapsed_secs: status
+                        .elapsed
+                        .saturating_sub(self.last_elapsed)
+                        .as_secs_f64(),
+                });
+            }
+            _ => {}
+        }
+        // the size of the output is polled in the background, so only events that are reported
+        // by the generation or conversion itself mark the start of the next file
+        if !matches!(event, ProgressEvent::BytesWritten { .. }) {
+            self.last_elapsed = status.elapsed;
+        }
+        self.elapsed = status.elapsed;
+    }
+}
+
+/// Records the progress events of a generation or conversion to build a [`TimingReport`]
+#[derive(Clone, Default)]
+pub struct TimingRecorder {
+    state: Arc<Mutex<RecorderState>>,
+}
+
+impl TimingRecorder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record the events reported to the returned progress, which are also reported to
+    /// `progress`
+    pub fn record(&self, progress: &Progress) -> Progress {
+        let state = self.state.clone();
+        progress.with_callback(move |event, status| state.lock().unwrap().update(event, status))
+    }
+
+    /// Build the report of the events recorded so far, with the sizes and row counts of the
+    /// tables from the manifest of the output
+    pub fn report(&self, command: &str, manifest: &Manifest) -> TimingReport {
+        let state = self.state.lock().unwrap();
+        let mut partition_timings = state.partition_timings.clone();
+        partition_timings.sort_by_key(|t| t.partition);
+
+        let tables: Vec<TableTiming> = manifest
+            .tables
+            .iter()
+            .map(|table| {
+                let files: Vec<&FileTiming> = state
+                    .files
+                    .iter()
+                    .filter(|f| f.table == table.name)
+                    .collect();
+                let input_bytes = files.iter().map(|f| f.input_bytes).sum();
+                let output_bytes = table.files.iter().map(|f| f.size).sum();
+                TableTiming {
+                    table: table.name.clone(),
+                    files: table.files.len(),
+                    rows: table.rows,
+                    input_bytes,
+                    output_bytes,
+                    compression_ratio: ratio(input_bytes, output_bytes),
+                    elapsed_secs: if files.is_empty() {
+                        None
+                    } else {
+                        Some(files.iter().map(|f| f.elapsed_secs).sum())
+                    },
+                }
+            })
+            .collect();
+
+        let input_bytes = tables.iter().map(|t| t.input_bytes).sum();
+        let output_bytes = tables.iter().map(|t| t.output_bytes).sum();
+        TimingReport {
+            command: command.to_owned(),
+            benchmark: manifest.benchmark.clone(),
+            scale: manifest.scale,
+            partitions: manifest.partitions,
+            format: manifest.format.clone(),
+            tpctools_version: env!("CARGO_PKG_VERSION").to_owned(),
+            created_time: manifest.created_time,
+            elapsed_secs: state.elapsed.as_secs_f64(),
+            rows: tables.iter().map(|t| t.rows).sum(),
+            input_bytes,
+            output_bytes,
+            compression_ratio: ratio(input_bytes, output_bytes),
+            peak_memory_bytes: peak_memory(Usage::Process),
+            peak_child_memory_bytes: peak_memory(Usage::Children),
+            partition_timings,
+            tables,
+            files: state.files.clone(),
+        }
+    }
+}
+
+impl TimingReport {
+    /// Write the report as JSON
+    pub fn write(&self, path: &Path) -> Result<()> {
+        let json = serde_json::to_string_pretty(self).map_err(|e| {
+            DataFusionError::Execution(format!("Failed to serialize report: {}", e))
+        })?;
+        fs::write(path, json)?;
+        Ok(())
+    }
+}
+
+fn ratio(input_bytes: u64, output_bytes: u64) -> Option<f64> {
+    if input_bytes > 0 && output_bytes > 0 {
+        Some(input_bytes as f64 / output_bytes as f64)
+    } else {
+        None
+    }
+}
+
+enum Usage {
+    Process,
+    /// The largest of the child processes that have been waited for
+    Children,
+}
+
+#[cfg(unix)]
+fn peak_memory(usage: Usage) -> Option<u64> {
+    let who = match usage {
+        Usage::Process => libc::RUSAGE_SELF,
+        Usage::Children => libc::RUSAGE_CHILDREN,
+    };
+    let mut rusage = std::mem::MaybeUninit::<libc::rusage>::zeroed();
+    // SAFETY: getrusage only writes to the struct it is given
+    let max_rss = unsafe {
+        if libc::getrusage(who, rusage.as_mut_ptr()) != 0 {
+            return None;
+        }
+        rusage.assume_init().ru_maxrss as u64
+    };
+    // ru_maxrss is in bytes on macOS and in kilobytes elsewhere, and zero when there were no
+    // child processes
+    match max_rss {
+        0 => None,
+        _ if cfg!(target_os = "macos") => Some(max_rss),
+        _ => Some(max_rss * 1024),
+    }
+}
+
+#[cfg(not(unix))]
+fn peak_memory(_usage: Usage) -> Option<u64> {
+    None
+}
@@ -0,0 +1,248 @@
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Instant, SystemTime};
+
+use datafusion::arrow::record_batch::RecordBatch;
+use datafusion::error::{DataFusionError, Result};
+use datafusion::prelude::*;
+use serde::Serialize;
+use tracing::{info, warn};
+
+use crate::validate::{compare_results, has_order_by, load_answers};
+use crate::{register_tables, Format, Tpc};
+
+/// Timings and row count for a single query across all iterations
+#[derive(Debug, Serialize)]
+pub struct QueryResult {
+    pub query: String,
+    pub rows: usize,
+    pub elapsed_ms: Vec<f64>,
+    pub min_ms: f64,
+    pub avg_ms: f64,
+    pub max_ms: f64,
+    /// Differences from the reference answers, when validation was requested
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub differences: Option<Vec<String>>,
+}
+
+/// Summary of a benchmark run, written out as JSON
+#[derive(Debug, Serialize)]
+pub struct RunSummary {
+    pub benchmark: String,
+    pub data_path: String,
+    pub format: String,
+    pub query_path: String,
+    pub iterations: usize,
+    pub start_time: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub answers_path: Option<String>,
+    pub queries: Vec<QueryResult>,
+}
+
+/// Register all tables of the benchmark, run every `.sql` file in `query_path` for the
+/// requested number of iterations and report timings per query.
+///
+/// When `answers_path` is specified, the results of the last iteration of each query are compared
+/// against the reference answer file `<query>.out`.
+pub async fn run_queries(
+    benchmark: &dyn Tpc,
+    benchmark_name: &str,
+    data_path: &str,
+    format: Format,
+    query_path: &str,
+    iterations: usize,
+    answers_path: Option<&str>,
+) -> Result<RunSummary> {
+    // the minimum time of a query is only defined when it runs at least once
+    if iterations == 0 {
+        return Err(DataFusionError::Execution(
+            "The number of iterations must be at least 1".to_owned(),
+        ));
+    }
+    let start_time = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default();
+
+    let ctx = SessionContext::new();
+    register_tables(&ctx, benchmark, data_path, format).await?;
+
+    let mut queries = vec![];
+    for query_file in find_queries(Path::new(query_path))? {
+        let query = query_file
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .unwrap_or_default()
+            .to_owned();
+        let sql = fs::read_to_string(&query_file)?;
+
+        let mut elapsed_ms = vec![];
+        let mut batches = vec![];
+        let mut rows = 0;
+        for i in 0..iterations {
+            let start = Instant::now();
+            batches = execute_query(&ctx, &sql).await?;
+            let elapsed = start.elapsed().as_secs_f64() * 1000.0;
+            rows = batches.iter().map(|b| b.num_rows()).sum();
+            info!(
+                query,
+                iteration = i,
+                elapsed_ms = elapsed,
+                rows,
+                "Query executed"
+            );
+            elapsed_ms.push(elapsed);
+        }
+
+        let differences = match answers_path {
+            Some(answers_path) => {
+                let answer_file = Path::new(answers_path).join(format!("{}.out", query));
+                if answer_file.exists() {
+                    let expected = load_answers(&answer_file)?;
+                    let differences = compare_results(&batches, &expected, has_order_by(&sql))?;
+                    if differences.is_empty() {
+                        info!(query, answers = %answer_file.display(), "Results match");
+                    } else {
+                        warn!(query, answers = %answer_file.display(), "Results do not match");
+                        for d in &differences {
+                            warn!(query, "{}", d);
+                        }
+                    }
+                    Some(differences)
+                } else {
+                    warn!(
+                        query,
+                        answers = %answer_file.display(),
+                        "No reference answers found"
+                    );
+                    None
+                }
+            }
+            None => None,
+        };
+
+        let min_ms = elapsed_ms.iter().cloned().fold(f64::INFINITY, f64::min);
+        let max_ms = elapsed_ms.iter().cloned().fold(0.0, f64::max);
+        let avg_ms = elapsed_ms.iter().sum::<f64>() / elapsed_ms.len().max(1) as f64;
+        queries.push(QueryResult {
+            query,
+            rows,
+            elapsed_ms,
+            min_ms,
+            avg_ms,
+            max_ms,
+            differences,
+        });
+    }
+
+    Ok(RunSummary {
+        benchmark: benchmark_name.to_owned(),
+        data_path: data_path.to_owned(),
+        format: format.to_string(),
+        query_path: query_path.to_owned(),
+        iterations,
+        start_time,
+        answers_path: answers_path.map(|s| s.to_owned()),
+        queries,
+    })
+}
+
+/// Write the run summary as JSON to `<output_dir>/<benchmark>-summary--<start_time>.json`
+pub fn write_summary(summary: &RunSummary, output_dir: &Path) -> Result<PathBuf> {
+    let path = output_dir.join(format!(
+        "{}-summary--{}.json",
+        summary.benchmark, summary.start_time
+    ));
+    let json = serde_json::to_string_pretty(summary)
+        .map_err(|e| DataFusionError::Execution(format!("Failed to serialize summary: {}", e)))?;
+    fs::write(&path, json)?;
+    Ok(path)
+}
+
+/// Execute all statements in a query file, returning the results of the last one. Some
+/// queries, such as TPC-H q15, create and drop a view around the actual query.
+pub(crate) async fn execute_query(ctx: &SessionContext, sql: &str) -> Result<Vec<RecordBatch>> {
+    let mut result = vec![];
+    for statement in sql.split(';').map(|s| s.trim()).filter(|s| !s.is_empty()) {
+        let batches = ctx.sql(statement).await?.collect().await?;
+        if is_query(statement) {
+            result = batches;
+        }
+    }
+    Ok(result)
+}
+
+fn is_query(statement: &str) -> bool {
+    let lower = statement
+        .lines()
+        .filter(|l| !l.trim_start().starts_with("--"))
+        .collect::<Vec<_>>()
+        .join(" ")
+        .trim_start()
+        .to_lowercase();
+    lower.starts_with("select") || lower.starts_with("with")
+}
+
+/// Find all `.sql` files in the query directory, ordered by query number so that `q2` runs
+/// before `q10`
+fn find_queries(query_path: &Path) -> Result<Vec<PathBuf>> {
+    let mut files = vec![];
+    for entry in fs::read_dir(query_path)? {
+        let path = entry?.path();
+        if path.extension().map(|ext| ext == "sql").unwrap_or(false) {
+            files.push(path);
+        }
+    }
+    files.sort_by_key(|path| {
+        let stem = path
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .unwrap_or_default()
+            .to_owned();
+        let number: String = stem.chars().filter(|c| c.is_ascii_digit()).collect();
+        (number.parse::<usize>().unwrap_or(usize::MAX), stem)
+    });
+    Ok(files)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tpch::TpcH;
+
+    #[tokio::test]
+    async fn run_rejects_zero_iterations() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().to_str().unwrap();
+        let err = run_queries(&TpcH::new(), "tpch", path, Format::Raw, path, 0, None)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("at least 1"), "{}", err);
+    }
+
+    #[test]
+    fn queries_in_numeric_order() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        for name in ["q10.sql", "q2.sql", "q1.sql", "notes.txt"] {
+            fs::write(dir.path().join(name), "SELECT 1")?;
+        }
+        let names: Vec<_> = find_queries(dir.path())?
+            .iter()
+            .map(|p| p.file_name().unwrap().to_str().unwrap().to_owned())
+            .collect();
+        assert_eq!(vec!["q1.sql", "q2.sql", "q10.sql"], names);
+        Ok(())
+    }
+}
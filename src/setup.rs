@@ -0,0 +1,406 @@
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Building the external data generators from their sources so that they can be used with
+//! `--generator-path`.
+
+use std::env;
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader, Error, ErrorKind, Result};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::Instant;
+
+use tracing::{debug, info};
+
+use crate::manifest::fnv1a;
+use crate::tpcds::dsdgen_command;
+use crate::{dbgen_command, generator_work_dir, run_logged, Tpc};
+
+/// How to build the generator of a benchmark and check the result
+struct BuildConfig {
+    /// Variables passed to `make`, which override the settings in `makefile.suite`
+    make_vars: Vec<(&'static str, &'static str)>,
+    /// Executables that the build must produce
+    binaries: [&'static str; 2],
+    /// Files that the generator reads at run time
+    data_files: Vec<&'static str>,
+    /// Table generated to check that the generator works, with the name that is passed to the
+    /// generator to select it
+    smoke_table: (&'static str, &'static str),
+}
+
+impl BuildConfig {
+    fn new(benchmark: &str) -> Result<Self> {
+        match benchmark {
+            "tpch" | "tpc-h" | "jcch" | "jcc-h" | "ssb" => Ok(Self {
+                make_vars: vec![
+                    ("CC", "gcc"),
+                    ("DATABASE", "ORACLE"),
+                    ("MACHINE", "LINUX"),
+                    ("WORKLOAD", if benchmark == "ssb" { "SSBM" } else { "TPCH" }),
+                ],
+                binaries: ["dbgen", "qgen"],
+                data_files: vec!["dists.dss"],
+                smoke_table: if benchmark == "ssb" {
+                    ("date", "d")
+                } else {
+                    ("nation", "n")
+                },
+            }),
+            "tpcds" | "tpc-ds" => Ok(Self {
+                make_vars: vec![
+                    ("OS", "LINUX"),
+                    ("LINUX_CC", "gcc"),
+                    // the sources define the same globals in several files, which GCC 10 and
+                    // later reject by default
+                    ("LINUX_CFLAGS", "-g -Wall -fcommon"),
+                ],
+                binaries: ["dsdgen", "dsqgen"],
+                data_files: vec!["tpcds.idx"],
+                smoke_table: ("income_band", "income_band"),
+            }),
+            _ => Err(Error::new(
+                ErrorKind::Unsupported,
+                format!("Cannot build a generator for {}", benchmark),
+            )),
+        }
+    }
+}
+
+/// Default directory for built generators: `$XDG_CACHE_HOME/tpctools`, or
+/// `$HOME/.cache/tpctools` when `XDG_CACHE_HOME` is not set
+pub fn default_cache_path() -> Result<PathBuf> {
+    match env::var_os("XDG_CACHE_HOME") {
+        Some(path) => Ok(PathBuf::from(path).join("tpctools")),
+        None => match env::var_os("HOME") {
+            Some(home) => Ok(PathBuf::from(home).join(".cache").join("tpctools")),
+            None => Err(Error::new(
+                ErrorKind::NotFound,
+                "Neither XDG_CACHE_HOME nor HOME is set, a cache path is required",
+            )),
+        },
+    }
+}
+
+/// Build the generator of a benchmark from `source_path`, which is either a checkout or a
+/// `.tar.gz`, `.tgz`, `.tar` or `.zip` archive of the sources, and install it under
+/// `cache_path` in a directory named after the benchmark and a hash of the sources. The
+/// sources are built in a copy, so a checkout is left unchanged. An existing installation
+/// for the same sources is reused unless `force` is set.
+///
+/// Returns the directory to use as `--generator-path`.
+pub fn setup_generator(
+    benchmark: &dyn Tpc,
+    benchmark_name: &str,
+    source_path: &Path,
+    cache_path: &Path,
+    force: bool,
+) -> Result<PathBuf> {
+    let config = BuildConfig::new(benchmark_name)?;
+
+    if !source_path.exists() {
+        return Err(Error::new(
+            ErrorKind::NotFound,
+            format!("Source path does not exist: {}", source_path.display()),
+        ));
+    }
+
+    let install_path = cache_path.join(format!(
+        "{}-{:016x}",
+        benchmark_name,
+        hash_source(source_path)?
+    ));
+    if install_path.exists() {
+        if !force {
+            info!(path = %install_path.display(), "Checking existing build");
+            verify_generator(benchmark, &config, &install_path)?;
+            return Ok(install_path);
+        }
+        info!(path = %install_path.display(), "Removing existing build");
+        fs::remove_dir_all(&install_path)?;
+    }
+
+    if !cache_path.exists() {
+        debug!(path = %cache_path.display(), "Creating directory");
+        fs::create_dir_all(cache_path)?;
+    }
+
+    let start = Instant::now();
+
+    // the build directory is removed if any of the steps fail
+    let build_root = generator_work_dir(cache_path.to_str().unwrap())?;
+    let source_root = build_root.path().join("source");
+    unpack_source(source_path, &source_root)?;
+
+    let (build_path, makefile) = find_makefile(&source_root)?.ok_or_else(|| {
+        Error::new(
+            ErrorKind::NotFound,
+            format!("No makefile found in {}", source_path.display()),
+        )
+    })?;
+
+    info!(
+        benchmark = benchmark_name,
+        path = %build_path.display(),
+        "Building generator"
+    );
+    let mut command = Command::new("make");
+    command.current_dir(&build_path);
+    if let Some(makefile) = makefile {
+        command.arg("-f").arg(makefile);
+    }
+    for (name, value) in &config.make_vars {
+        command.arg(format!("{}={}", name, value));
+    }
+    // the output of make is written to `<cache>/logs/make-<benchmark>.log`
+    let log_name = format!("make-{}", benchmark_name);
+    run_logged(&mut command, cache_path.to_str().unwrap(), &log_name).map_err(|e| {
+        Error::other(format!(
+            "Failed to build {} in {}: {}",
+            benchmark_name,
+            build_path.display(),
+            e
+        ))
+    })?;
+
+    verify_generator(benchmark, &config, &build_path)?;
+
+    info!(
+        benchmark = benchmark_name,
+        path = %install_path.display(),
+        "Installing generator"
+    );
+    fs::rename(&build_path, &install_path)?;
+
+    info!(
+        benchmark = benchmark_name,
+        elapsed = ?start.elapsed(),
+        "Built generator"
+    );
+
+    Ok(install_path)
+}
+
+/// Check that the binaries and data files exist and that the generator produces the expected
+/// number of rows for a small table
+fn verify_generator(benchmark: &dyn Tpc, config: &BuildConfig, path: &Path) -> Result<()> {
+    for binary in config.binaries {
+        if !is_executable(&path.join(binary))? {
+            return Err(Error::new(
+                ErrorKind::NotFound,
+                format!("{} was not built in {}", binary, path.display()),
+            ));
+        }
+    }
+    for data_file in &config.data_files {
+        if !path.join(data_file).exists() {
+            return Err(Error::new(
+                ErrorKind::NotFound,
+                format!("{} is missing from {}", data_file, path.display()),
+            ));
+        }
+    }
+
+    let generator_path = path.to_str().unwrap();
+    // generate next to the generator rather than in its directory
+    let work_dir = generator_work_dir(path.parent().unwrap().to_str().unwrap())?;
+    let (table, table_arg) = config.smoke_table;
+    let mut command = match config.binaries[0] {
+        "dsdgen" => {
+            let mut command = dsdgen_command(generator_path, work_dir.path())?;
+            command
+                .arg("-FORCE")
+                .arg("-DIR")
+                .arg(work_dir.path())
+                .arg("-TABLE")
+                .arg(table_arg)
+                .arg("-SCALE")
+                .arg("1");
+            command
+        }
+        _ => {
+            let mut command = dbgen_command(generator_path, work_dir.path())?;
+            command
+                .arg("-f")
+                .arg("-s")
+                .arg("1")
+                .arg("-T")
+                .arg(table_arg);
+            command
+        }
+    };
+    let output = command.output()?;
+    if !output.status.success() {
+        return Err(Error::other(format!(
+            "{} failed to generate {}: {}",
+            config.binaries[0],
+            table,
+            String::from_utf8_lossy(&output.stderr)
+        )));
+    }
+
+    let filename = work_dir
+        .path()
+        .join(format!("{}.{}", table, benchmark.get_table_ext()));
+    let rows = BufReader::new(File::open(&filename)?).lines().count() as u64;
+    let expected = benchmark.get_row_count(table, 1.0);
+    if Some(rows) != expected {
+        return Err(Error::other(format!(
+            "{} generated {} rows for {} instead of {:?}",
+            config.binaries[0], rows, table, expected
+        )));
+    }
+    info!(
+        generator = config.binaries[0],
+        path = %path.display(),
+        table,
+        rows,
+        "Verified generator"
+    );
+    Ok(())
+}
+
+#[cfg(unix)]
+fn is_executable(path: &Path) -> Result<bool> {
+    use std::os::unix::fs::PermissionsExt;
+    Ok(path.is_file() && fs::metadata(path)?.permissions().mode() & 0o111 != 0)
+}
+
+#[cfg(not(unix))]
+fn is_executable(path: &Path) -> Result<bool> {
+    Ok(path.is_file())
+}
+
+/// Extract an archive, or copy a checkout, to `dest_path`
+fn unpack_source(source_path: &Path, dest_path: &Path) -> Result<()> {
+    if source_path.is_dir() {
+        debug!(
+            source = %source_path.display(),
+            dest = %dest_path.display(),
+            "Copying source"
+        );
+        return copy_dir(source_path, dest_path);
+    }
+
+    fs::create_dir(dest_path)?;
+    let name = source_path.to_string_lossy().to_lowercase();
+    let mut command = if name.ends_with(".zip") {
+        let mut command = Command::new("unzip");
+        command.arg("-q").arg(source_path).arg("-d").arg(dest_path);
+        command
+    } else if name.ends_with(".tar.gz") || name.ends_with(".tgz") || name.ends_with(".tar") {
+        let mut command = Command::new("tar");
+        command.arg("-xf").arg(source_path).arg("-C").arg(dest_path);
+        command
+    } else {
+        return Err(Error::new(
+            ErrorKind::Unsupported,
+            format!("Unsupported source archive: {}", source_path.display()),
+        ));
+    };
+    debug!(
+        source = %source_path.display(),
+        dest = %dest_path.display(),
+        "Extracting source"
+    );
+    let status = command.status()?;
+    if !status.success() {
+        return Err(Error::other(format!(
+            "Failed to extract {}: {}",
+            source_path.display(),
+            status
+        )));
+    }
+    Ok(())
+}
+
+fn copy_dir(source_path: &Path, dest_path: &Path) -> Result<()> {
+    fs::create_dir(dest_path)?;
+    for entry in fs::read_dir(source_path)? {
+        let entry = entry?;
+        let dest = dest_path.join(entry.file_name());
+        if entry.file_type()?.is_dir() {
+            copy_dir(&entry.path(), &dest)?;
+        } else {
+            fs::copy(entry.path(), dest)?;
+        }
+    }
+    Ok(())
+}
+
+/// Find the directory to build in. The TPC sources ship a `makefile.suite` template (in the
+/// `dbgen` or `tools` directory of the official archives) while some forks ship a ready-made
+/// makefile at the top level. Returns the directory and the name of the `makefile.suite`, if
+/// there is one.
+fn find_makefile(path: &Path) -> Result<Option<(PathBuf, Option<String>)>> {
+    let mut subdirs = vec![];
+    let mut has_makefile = false;
+    let mut entries = fs::read_dir(path)?.collect::<Result<Vec<_>>>()?;
+    entries.sort_by_key(|e| e.file_name());
+    for entry in entries {
+        let name = entry.file_name().to_string_lossy().to_string();
+        if entry.file_type()?.is_dir() {
+            if !name.starts_with('.') {
+                subdirs.push(entry.path());
+            }
+        } else if name.eq_ignore_ascii_case("makefile.suite") {
+            return Ok(Some((path.to_owned(), Some(name))));
+        } else if name == "makefile" || name == "Makefile" {
+            has_makefile = true;
+        }
+    }
+    for subdir in subdirs {
+        if let Some(found) = find_makefile(&subdir)? {
+            return Ok(Some(found));
+        }
+    }
+    Ok(if has_makefile {
+        Some((path.to_owned(), None))
+    } else {
+        None
+    })
+}
+
+/// Hash of an archive, or of the names and contents of the files of a checkout, so that
+/// different versions of the sources are installed in different directories
+fn hash_source(path: &Path) -> Result<u64> {
+    if !path.is_dir() {
+        return Ok(fnv1a(&fs::read(path)?));
+    }
+    let mut files = vec![];
+    list_source_files(path, path, &mut files)?;
+    files.sort();
+    let mut bytes = vec![];
+    for file in files {
+        bytes.extend_from_slice(file.to_string_lossy().as_bytes());
+        bytes.push(0);
+        bytes.extend_from_slice(&fnv1a(&fs::read(path.join(&file))?).to_le_bytes());
+    }
+    Ok(fnv1a(&bytes))
+}
+
+fn list_source_files(root: &Path, path: &Path, files: &mut Vec<PathBuf>) -> Result<()> {
+    for entry in fs::read_dir(path)? {
+        let entry = entry?;
+        // skip version control metadata
+        if entry.file_name().to_string_lossy().starts_with('.') {
+            continue;
+        }
+        if entry.file_type()?.is_dir() {
+            list_source_files(root, &entry.path(), files)?;
+        } else {
+            files.push(entry.path().strip_prefix(root).unwrap().to_owned());
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,352 @@
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! The Star Schema Benchmark, which is derived from TPC-H. The data is generated with
+//! `ssb-dbgen`, which takes the same options as the TPC-H `dbgen`.
+
+use std::fs;
+use std::io::{Error, ErrorKind, Result};
+use std::path::Path;
+use std::thread;
+use std::time::Instant;
+
+use async_trait::async_trait;
+use datafusion::arrow::datatypes::{DataType, Field, Schema};
+use tracing::{debug, info, info_span};
+
+use crate::progress::{Progress, ProgressEvent};
+use crate::{
+    check_scale, dbgen_command, generator_work_dir, join_generators, move_or_copy, run_logged,
+    unknown_table, ForeignKey, TableType, Tpc,
+};
+
+/// Approximate size of the `.tbl` files at scale factor 1, which is used to estimate the time
+/// remaining
+const RAW_BYTES_PER_SCALE_FACTOR: f64 = 0.6e9;
+
+pub struct Ssb {}
+
+impl Ssb {
+    pub fn new() -> Self {
+        Self {}
+    }
+}
+
+impl Default for Ssb {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl Tpc for Ssb {
+    fn generate(
+        &self,
+        scale: f64,
+        partitions: usize,
+        generator_path: &str,
+        output_path: &str,
+        seed: Option<u64>,
+        progress: &Progress,
+    ) -> Result<()> {
+        check_scale(scale)?;
+        if seed.is_some() {
+            return Err(Error::new(
+                ErrorKind::Unsupported,
+                "ssb-dbgen does not support setting a seed",
+            ));
+        }
+
+        if !Path::new(&output_path).exists() {
+            debug!(path = output_path, "Creating directory");
+            fs::create_dir(output_path)?;
+        }
+
+        // each partition is generated in its own working directory
+        let work_dirs = (0..partitions)
+            .map(|_| generator_work_dir(output_path))
+            .collect::<Result<Vec<_>>>()?;
+
+        let mut handles = vec![];
+
+        let start = Instant::now();
+
+        progress.report(ProgressEvent::GenerationStarted {
+            children: partitions,
+            expected_bytes: Some((RAW_BYTES_PER_SCALE_FACTOR * scale) as u64),
+        });
+        let watcher = progress.watch(output_path, &self.get_table_names());
+
+        for (i, work_dir) in (1..=partitions).zip(&work_dirs) {
+            let mut command = dbgen_command(generator_path, work_dir.path())?;
+            let output_path = output_path.to_owned();
+            let progress = progress.clone();
+            let span = info_span!("partition", part = i);
+            handles.push(thread::spawn(move || {
+                let _span = span.entered();
+                info!("Generating partition {} of {}", i, partitions);
+                command
+                    .arg("-f")
+                    .arg("-s")
+                    .arg(format!("{}", scale))
+                    .arg("-T")
+                    .arg("a");
+                if partitions > 1 {
+                    command
+                        .arg("-C")
+                        .arg(format!("{}", partitions))
+                        .arg("-S")
+                        .arg(format!("{}", i));
+                }
+                let result = run_logged(&mut command, &output_path, &format!("dbgen-{}", i));
+                progress.report(ProgressEvent::ChildFinished { child: i });
+                result
+            }));
+        }
+
+        // wait for all threads to finish
+        join_generators(handles)?;
+
+        let duration = start.elapsed();
+
+        info!(
+            scale,
+            partitions,
+            elapsed = ?duration,
+            "Generated SSB data"
+        );
+
+        for table in self.get_table_names() {
+            let output_dir = format!("{}/{}.tbl", output_path, table);
+            if !Path::new(&output_dir).exists() {
+                debug!(path = %output_dir, "Creating directory");
+                fs::create_dir(&output_dir)?;
+            }
+
+            // tables that are too small to be split are written to a single file even when
+            // generating partitions
+            for work_dir in &work_dirs {
+                let filename = format!("{}/{}.tbl", work_dir.path().display(), table);
+                let filename2 = format!("{}/part-0.tbl", output_dir);
+                if Path::new(&filename).exists() {
+                    move_or_copy(Path::new(&filename), Path::new(&filename2))?;
+                }
+            }
+
+            for (i, work_dir) in (1..=partitions).zip(&work_dirs) {
+                let filename = format!("{}/{}.tbl.{}", work_dir.path().display(), table, i);
+                let filename2 = format!("{}/part-{}.tbl", output_dir, i);
+                if Path::new(&filename).exists() {
+                    move_or_copy(Path::new(&filename), Path::new(&filename2))?;
+                }
+            }
+        }
+
+        drop(watcher);
+        progress.report(ProgressEvent::Finished);
+
+        Ok(())
+    }
+
+    fn get_table_names(&self) -> Vec<&str> {
+        vec!["customer", "date", "lineorder", "part", "supplier"]
+    }
+
+    fn get_table_ext(&self) -> &str {
+        "tbl"
+    }
+
+    fn get_schema(&self, table: &str) -> Result<Schema> {
+        // keys use the same types as the TPC-H schema. Prices are generated as whole numbers and
+        // dates as integers in YYYYMMDD form, which are the keys of the date table.
+        Ok(match table {
+            "lineorder" => Schema::new(vec![
+                Field::new("lo_orderkey", DataType::Int64, false),
+                Field::new("lo_linenumber", DataType::Int32, false),
+                Field::new("lo_custkey", DataType::Int64, false),
+                Field::new("lo_partkey", DataType::Int64, false),
+                Field::new("lo_suppkey", DataType::Int64, false),
+                Field::new("lo_orderdate", DataType::Int32, false),
+                Field::new("lo_orderpriority", DataType::Utf8, false),
+                Field::new("lo_shippriority", DataType::Int32, false),
+                Field::new("lo_quantity", DataType::Int32, false),
+                Field::new("lo_extendedprice", DataType::Int64, false),
+                Field::new("lo_ordtotalprice", DataType::Int64, false),
+                Field::new("lo_discount", DataType::Int32, false),
+                Field::new("lo_revenue", DataType::Int64, false),
+                Field::new("lo_supplycost", DataType::Int64, false),
+                Field::new("lo_tax", DataType::Int32, false),
+                Field::new("lo_commitdate", DataType::Int32, false),
+                Field::new("lo_shipmode", DataType::Utf8, false),
+                Field::new("ignore", DataType::Utf8, true),
+            ]),
+
+            "customer" => Schema::new(vec![
+                Field::new("c_custkey", DataType::Int64, false),
+                Field::new("c_name", DataType::Utf8, false),
+                Field::new("c_address", DataType::Utf8, false),
+                Field::new("c_city", DataType::Utf8, false),
+                Field::new("c_nation", DataType::Utf8, false),
+                Field::new("c_region", DataType::Utf8, false),
+                Field::new("c_phone", DataType::Utf8, false),
+                Field::new("c_mktsegment", DataType::Utf8, false),
+                Field::new("ignore", DataType::Utf8, true),
+            ]),
+
+            "supplier" => Schema::new(vec![
+                Field::new("s_suppkey", DataType::Int64, false),
+                Field::new("s_name", DataType::Utf8, false),
+                Field::new("s_address", DataType::Utf8, false),
+                Field::new("s_city", DataType::Utf8, false),
+                Field::new("s_nation", DataType::Utf8, false),
+                Field::new("s_region", DataType::Utf8, false),
+                Field::new("s_phone", DataType::Utf8, false),
+                Field::new("ignore", DataType::Utf8, true),
+            ]),
+
+            "part" => Schema::new(vec![
+                Field::new("p_partkey", DataType::Int64, false),
+                Field::new("p_name", DataType::Utf8, false),
+                Field::new("p_mfgr", DataType::Utf8, false),
+                Field::new("p_category", DataType::Utf8, false),
+                Field::new("p_brand1", DataType::Utf8, false),
+                Field::new("p_color", DataType::Utf8, false),
+                Field::new("p_type", DataType::Utf8, false),
+                Field::new("p_size", DataType::Int32, false),
+                Field::new("p_container", DataType::Utf8, false),
+                Field::new("ignore", DataType::Utf8, true),
+            ]),
+
+            "date" => Schema::new(vec![
+                Field::new("d_datekey", DataType::Int32, false),
+                Field::new("d_date", DataType::Utf8, false),
+                Field::new("d_dayofweek", DataType::Utf8, false),
+                Field::new("d_month", DataType::Utf8, false),
+                Field::new("d_year", DataType::Int32, false),
+                Field::new("d_yearmonthnum", DataType::Int32, false),
+                Field::new("d_yearmonth", DataType::Utf8, false),
+                Field::new("d_daynuminweek", DataType::Int32, false),
+                Field::new("d_daynuminmonth", DataType::Int32, false),
+                Field::new("d_daynuminyear", DataType::Int32, false),
+                Field::new("d_monthnuminyear", DataType::Int32, false),
+                Field::new("d_weeknuminyear", DataType::Int32, false),
+                Field::new("d_sellingseason", DataType::Utf8, false),
+                Field::new("d_lastdayinweekfl", DataType::Int32, false),
+                Field::new("d_lastdayinmonthfl", DataType::Int32, false),
+                Field::new("d_holidayfl", DataType::Int32, false),
+                Field::new("d_weekdayfl", DataType::Int32, false),
+                Field::new("ignore", DataType::Utf8, true),
+            ]),
+
+            _ => return Err(unknown_table("SSB", table)),
+        })
+    }
+
+    fn get_primary_key(&self, table: &str) -> Result<Vec<&str>> {
+        Ok(match table {
+            "lineorder" => vec!["lo_orderkey", "lo_linenumber"],
+            "customer" => vec!["c_custkey"],
+            "supplier" => vec!["s_suppkey"],
+            "part" => vec!["p_partkey"],
+            "date" => vec!["d_datekey"],
+            _ => return Err(unknown_table("SSB", table)),
+        })
+    }
+
+    fn get_foreign_keys(&self, table: &str) -> Result<Vec<ForeignKey>> {
+        Ok(match table {
+            "lineorder" => vec![
+                ForeignKey::new(&["lo_custkey"], "customer", &["c_custkey"]),
+                ForeignKey::new(&["lo_partkey"], "part", &["p_partkey"]),
+                ForeignKey::new(&["lo_suppkey"], "supplier", &["s_suppkey"]),
+                ForeignKey::new(&["lo_orderdate"], "date", &["d_datekey"]),
+                ForeignKey::new(&["lo_commitdate"], "date", &["d_datekey"]),
+            ],
+            "customer" | "supplier" | "part" | "date" => vec![],
+            _ => return Err(unknown_table("SSB", table)),
+        })
+    }
+
+    fn get_table_type(&self, table: &str) -> Result<TableType> {
+        match table {
+            "lineorder" => Ok(TableType::Fact),
+            "customer" | "supplier" | "part" | "date" => Ok(TableType::Dimension),
+            _ => Err(unknown_table("SSB", table)),
+        }
+    }
+
+    fn get_row_count(&self, table: &str, scale: f64) -> Option<u64> {
+        match table {
+            "customer" => Some((30_000.0 * scale) as u64),
+            "supplier" => Some((2_000.0 * scale) as u64),
+            // the part table grows logarithmically with the scale factor
+            "part" if scale >= 1.0 => Some(200_000 * (1.0 + scale.log2().floor()) as u64),
+            "part" => None,
+            "date" => Some(2_556),
+            // the number of line items per order is random
+            "lineorder" => None,
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unknown_table_is_an_error() {
+        let ssb = Ssb::new();
+        for err in [
+            ssb.get_schema("lineitem").unwrap_err(),
+            ssb.get_primary_key("lineitem").unwrap_err(),
+            ssb.get_foreign_keys("lineitem").unwrap_err(),
+            ssb.get_table_type("lineitem").unwrap_err(),
+        ] {
+            assert_eq!(ErrorKind::InvalidInput, err.kind());
+            assert_eq!("Unknown SSB table 'lineitem'", err.to_string());
+        }
+        assert_eq!(None, ssb.get_row_count("lineitem", 1.0));
+        assert_eq!(Some(30_000), ssb.get_row_count("customer", 1.0));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn generate_fails_when_dbgen_fails() -> Result<()> {
+        use std::os::unix::fs::PermissionsExt;
+
+        let generator_dir = tempfile::tempdir()?;
+        let dbgen = generator_dir.path().join("dbgen");
+        fs::write(&dbgen, "#!/bin/sh\necho 'open failed' >&2\nexit 1\n")?;
+        fs::set_permissions(&dbgen, fs::Permissions::from_mode(0o755))?;
+        let output_dir = tempfile::tempdir()?;
+
+        let err = Ssb::new()
+            .generate(
+                1.0,
+                2,
+                generator_dir.path().to_str().unwrap(),
+                output_dir.path().to_str().unwrap(),
+                None,
+                &Progress::none(),
+            )
+            .unwrap_err();
+        let log_path = output_dir.path().join("logs").join("dbgen-1.log");
+        assert!(
+            err.to_string().contains(&log_path.display().to_string()),
+            "{}",
+            err
+        );
+        assert_eq!("open failed\n", fs::read_to_string(log_path)?);
+        Ok(())
+    }
+}
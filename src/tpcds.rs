@@ -11,15 +11,31 @@
 // limitations under the License.
 
 use async_trait::async_trait;
+use datafusion::arrow::csv;
 use datafusion::arrow::datatypes::{DataType, Field, Schema};
-use std::fs;
-use std::io::Result;
+use datafusion::error::DataFusionError;
+use datafusion::parquet::arrow::ArrowWriter;
+use datafusion::parquet::basic::Compression;
+use datafusion::parquet::file::properties::WriterProperties;
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader, Result};
 use std::path::Path;
-use std::process::Command;
+use std::process::{Command, Stdio};
+use std::sync::Arc;
 use std::thread;
 use std::time::Instant;
+use tracing::{debug, info, info_span};
 
-use crate::{move_or_copy, Tpc};
+use crate::progress::{Progress, ProgressEvent};
+use crate::{
+    check_scale, child_log, generator_work_dir, join_generators, move_or_copy, run_logged,
+    unknown_table, with_ignore_column, ForeignKey, TableType, Tpc,
+};
+
+/// Approximate size of the `.dat` files at scale factor 1, which is used to estimate the time
+/// remaining
+const RAW_BYTES_PER_SCALE_FACTOR: f64 = 1.2e9;
 
 pub struct TpcDs {}
 
@@ -29,58 +45,526 @@ impl TpcDs {
     }
 }
 
+/// Surrogate key columns are named after the dimension table they refer to, for example
+/// `ss_sold_date_sk` and `c_first_sales_date_sk` both refer to `date_dim.d_date_sk`
+const SURROGATE_KEYS: [(&str, &str, &str); 17] = [
+    ("_date_sk", "date_dim", "d_date_sk"),
+    ("_time_sk", "time_dim", "t_time_sk"),
+    ("_item_sk", "item", "i_item_sk"),
+    ("_customer_sk", "customer", "c_customer_sk"),
+    ("_cdemo_sk", "customer_demographics", "cd_demo_sk"),
+    ("_hdemo_sk", "household_demographics", "hd_demo_sk"),
+    ("_addr_sk", "customer_address", "ca_address_sk"),
+    ("_store_sk", "store", "s_store_sk"),
+    ("_promo_sk", "promotion", "p_promo_sk"),
+    ("_reason_sk", "reason", "r_reason_sk"),
+    ("_call_center_sk", "call_center", "cc_call_center_sk"),
+    ("_catalog_page_sk", "catalog_page", "cp_catalog_page_sk"),
+    ("_ship_mode_sk", "ship_mode", "sm_ship_mode_sk"),
+    ("_warehouse_sk", "warehouse", "w_warehouse_sk"),
+    ("_web_page_sk", "web_page", "wp_web_page_sk"),
+    ("_web_site_sk", "web_site", "web_site_sk"),
+    ("_income_band_sk", "income_band", "ib_income_band_sk"),
+];
+
+/// Scale factors for which the specification defines the row counts in `ROW_COUNTS`
+const SCALE_FACTORS: [f64; 9] = [
+    1.0, 10.0, 100.0, 300.0, 1000.0, 3000.0, 10000.0, 30000.0, 100000.0,
+];
+
+/// Row counts of the tables that grow with the scale factor, for each of `SCALE_FACTORS`
+const ROW_COUNTS: [(&str, [u64; 9]); 18] = [
+    ("call_center", [6, 24, 30, 36, 42, 48, 54, 60, 60]),
+    (
+        "catalog_page",
+        [
+            11_718, 12_000, 20_400, 26_000, 30_000, 36_000, 40_000, 46_000, 50_000,
+        ],
+    ),
+    (
+        "catalog_returns",
+        [
+            144_067,
+            1_439_749,
+            14_404_374,
+            43_193_472,
+            143_996_756,
+            432_018_033,
+            1_440_033_112,
+            4_319_925_093,
+            14_400_175_879,
+        ],
+    ),
+    (
+        "catalog_sales",
+        [
+            1_441_548,
+            14_401_261,
+            143_997_065,
+            431_969_836,
+            1_439_980_416,
+            4_320_078_880,
+            14_399_964_710,
+            43_200_404_822,
+            143_999_334_399,
+        ],
+    ),
+    (
+        "customer",
+        [
+            100_000,
+            500_000,
+            2_000_000,
+            5_000_000,
+            12_000_000,
+            30_000_000,
+            65_000_000,
+            80_000_000,
+            100_000_000,
+        ],
+    ),
+    (
+        "customer_address",
+        [
+            50_000, 250_000, 1_000_000, 2_500_000, 6_000_000, 15_000_000, 32_500_000, 40_000_000,
+            50_000_000,
+        ],
+    ),
+    (
+        "inventory",
+        [
+            11_745_000,
+            133_110_000,
+            399_330_000,
+            585_684_000,
+            783_000_000,
+            1_033_560_000,
+            1_311_525_000,
+            1_627_857_000,
+            1_965_337_830,
+        ],
+    ),
+    (
+        "item",
+        [
+            18_000, 102_000, 204_000, 264_000, 300_000, 360_000, 402_000, 462_000, 502_000,
+        ],
+    ),
+    (
+        "promotion",
+        [300, 500, 1_000, 1_300, 1_500, 1_800, 2_000, 2_300, 2_500],
+    ),
+    ("reason", [35, 45, 55, 60, 65, 67, 70, 72, 75]),
+    (
+        "store",
+        [12, 102, 402, 804, 1_002, 1_350, 1_500, 1_704, 1_902],
+    ),
+    (
+        "store_returns",
+        [
+            287_514,
+            2_875_432,
+            28_795_080,
+            86_393_244,
+            287_999_764,
+            863_989_652,
+            2_879_970_104,
+            8_639_952_111,
+            28_800_018_820,
+        ],
+    ),
+    (
+        "store_sales",
+        [
+            2_880_404,
+            28_800_991,
+            287_997_024,
+            864_001_869,
+            2_879_987_999,
+            8_639_936_081,
+            28_799_983_563,
+            86_399_341_874,
+            287_997_818_084,
+        ],
+    ),
+    ("warehouse", [5, 10, 15, 17, 20, 22, 25, 27, 30]),
+    (
+        "web_page",
+        [60, 200, 2_040, 2_604, 3_000, 3_600, 4_002, 4_602, 5_004],
+    ),
+    (
+        "web_returns",
+        [
+            71_763,
+            719_217,
+            7_197_670,
+            21_599_377,
+            71_997_522,
+            216_003_761,
+            720_020_485,
+            2_160_007_345,
+            7_199_904_459,
+        ],
+    ),
+    (
+        "web_sales",
+        [
+            719_384,
+            7_197_566,
+            72_001_237,
+            216_009_853,
+            720_000_376,
+            2_159_968_881,
+            7_199_963_324,
+            21_600_036_511,
+            71_999_670_164,
+        ],
+    ),
+    ("web_site", [30, 42, 24, 30, 54, 66, 78, 84, 96]),
+];
+
+/// Returns tables are not generated on their own but are written by dsdgen along with the
+/// corresponding sales table
+const CHILD_TABLES: [(&str, &str); 3] = [
+    ("catalog_sales", "catalog_returns"),
+    ("store_sales", "store_returns"),
+    ("web_sales", "web_returns"),
+];
+
+/// dsdgen arguments for a seed, which otherwise uses its default seed of 19620718
+fn seed_args(seed: Option<u64>) -> Vec<String> {
+    match seed {
+        Some(seed) => vec!["-RNGSEED".to_owned(), seed.to_string()],
+        None => vec![],
+    }
+}
+
+/// Command to run dsdgen from `generator_path` in `work_dir`, reading the distributions from
+/// the generator directory so that nothing is written to it
+pub(crate) fn dsdgen_command(generator_path: &str, work_dir: &Path) -> Result<Command> {
+    // the working directory changes, so relative paths no longer refer to the generator
+    let generator_path = fs::canonicalize(generator_path)?;
+    let mut command = Command::new(generator_path.join("dsdgen"));
+    command
+        .current_dir(work_dir)
+        .arg("-DISTRIBUTIONS")
+        .arg(generator_path.join("tpcds.idx"));
+    Ok(command)
+}
+
+impl TpcDs {
+    /// Generate the data straight to Parquet by running dsdgen with `-FILTER Y` for each table
+    /// and partition and converting its output as it is streamed, so that no `.dat` files are
+    /// written. Partition `n` of each table is written to `<table>.parquet/part-<n - 1>.parquet`.
+    pub fn generate_parquet(
+        &self,
+        scale: f64,
+        partitions: usize,
+        generator_path: &str,
+        output_path: &str,
+        seed: Option<u64>,
+        progress: &Progress,
+    ) -> datafusion::error::Result<()> {
+        check_scale(scale)?;
+        let start = Instant::now();
+
+        for table in self.get_table_names() {
+            let output_dir = format!("{}/{}.parquet", output_path, table);
+            if !Path::new(&output_dir).exists() {
+                debug!(path = %output_dir, "Creating directory");
+                fs::create_dir(&output_dir)?;
+            }
+        }
+
+        // the size of the compressed output is not known in advance
+        progress.report(ProgressEvent::GenerationStarted {
+            children: partitions,
+            expected_bytes: None,
+        });
+        let watcher = progress.watch(output_path, &self.get_table_names());
+
+        let mut handles = vec![];
+        for i in 1..=partitions {
+            let generator_path = generator_path.to_owned();
+            let output_path = output_path.to_owned();
+            let progress = progress.clone();
+            let span = info_span!("partition", part = i);
+            handles.push(thread::spawn(move || -> Result<()> {
+                let _span = span.entered();
+                info!("Generating partition {} of {}", i, partitions);
+                let tpcds = TpcDs::new();
+                for table in tpcds.get_table_names() {
+                    if CHILD_TABLES.iter().any(|(_, child)| *child == table) {
+                        continue;
+                    }
+                    pipe_table(
+                        table,
+                        scale,
+                        i,
+                        partitions,
+                        &generator_path,
+                        &output_path,
+                        seed,
+                    )
+                    .map_err(std::io::Error::other)?;
+                }
+                progress.report(ProgressEvent::ChildFinished { child: i });
+                Ok(())
+            }));
+        }
+
+        // wait for all threads to finish
+        join_generators(handles)?;
+
+        drop(watcher);
+        progress.report(ProgressEvent::Finished);
+
+        info!(
+            scale,
+            partitions,
+            elapsed = ?start.elapsed(),
+            "Generated TPC-DS data"
+        );
+
+        Ok(())
+    }
+}
+
+/// Run dsdgen for one table and partition and write its output to Parquet. The output of a
+/// sales table also contains the rows of its returns table, which are told apart by their
+/// number of fields.
+fn pipe_table(
+    table: &str,
+    scale: f64,
+    part: usize,
+    partitions: usize,
+    generator_path: &str,
+    output_path: &str,
+    seed: Option<u64>,
+) -> datafusion::error::Result<()> {
+    let mut tables = vec![table];
+    if let Some((_, child)) = CHILD_TABLES.iter().find(|(parent, _)| *parent == table) {
+        tables.push(child);
+    }
+
+    let mut outputs: HashMap<usize, TableOutput> = HashMap::new();
+    for table in tables {
+        let filename = format!(
+            "{}/{}.parquet/part-{}.parquet",
+            output_path,
+            table,
+            part - 1
+        );
+        // every row ends with a delimiter, which is read into a trailing `ignore` column like
+        // the raw files converted by `convert_to_parquet`
+        let schema = with_ignore_column(TpcDs::new().get_schema(table)?);
+        let output = TableOutput::new(Arc::new(schema), filename);
+        outputs.insert(output.schema.fields().len(), output);
+    }
+
+    debug!(table, "Generating table");
+    let work_dir = generator_work_dir(output_path)?;
+    // stdout is the data, so only stderr is written to the log
+    let (log, _) = child_log(output_path, &format!("dsdgen-{}", part))?;
+    let mut child = dsdgen_command(generator_path, work_dir.path())?
+        .arg("-FORCE")
+        .arg("-QUIET")
+        .arg("Y")
+        .arg("-FILTER")
+        .arg("Y")
+        .arg("-TABLE")
+        .arg(table)
+        .arg("-SCALE")
+        .arg(format!("{}", scale))
+        .arg("-CHILD")
+        .arg(format!("{}", part))
+        .arg("-PARALLEL")
+        .arg(format!("{}", partitions))
+        .args(seed_args(seed))
+        .stdout(Stdio::piped())
+        .stderr(log)
+        .spawn()?;
+
+    let reader = BufReader::new(child.stdout.take().unwrap());
+    if let Err(e) = split_rows(reader, table, &mut outputs) {
+        // otherwise dsdgen blocks on the full pipe and is never reaped
+        let _ = child.kill();
+        let _ = child.wait();
+        return Err(e);
+    }
+
+    let status = child.wait()?;
+    if !status.success() {
+        return Err(DataFusionError::Execution(format!(
+            "dsdgen failed for table {} partition {}: {}",
+            table, part, status
+        )));
+    }
+
+    for output in outputs.into_values() {
+        output.close()?;
+    }
+    Ok(())
+}
+
+/// Write each row of dsdgen output to the table with its number of fields
+fn split_rows(
+    mut reader: impl BufRead,
+    table: &str,
+    outputs: &mut HashMap<usize, TableOutput>,
+) -> datafusion::error::Result<()> {
+    let mut line = vec![];
+    while reader.read_until(b'\n', &mut line)? > 0 {
+        let field_count = line.iter().filter(|b| **b == b'|').count() + 1;
+        match outputs.get_mut(&field_count) {
+            Some(output) => output.write_line(&line)?,
+            None => {
+                return Err(DataFusionError::Execution(format!(
+                    "Unexpected row in dsdgen output for {}: {}",
+                    table,
+                    String::from_utf8_lossy(&line)
+                )))
+            }
+        }
+        line.clear();
+    }
+    Ok(())
+}
+
+/// Converts the rows of one table to Parquet as they are read. The file is only created once
+/// the first row is read because dsdgen only generates the smaller tables for the first
+/// partition.
+struct TableOutput {
+    schema: Arc<Schema>,
+    filename: String,
+    decoder: csv::reader::Decoder,
+    writer: Option<ArrowWriter<File>>,
+}
+
+impl TableOutput {
+    fn new(schema: Arc<Schema>, filename: String) -> Self {
+        let decoder = csv::ReaderBuilder::new()
+            .with_schema(schema.clone())
+            .has_header(false)
+            .with_delimiter(b'|')
+            .with_batch_size(8192)
+            .build_decoder();
+        Self {
+            schema,
+            filename,
+            decoder,
+            writer: None,
+        }
+    }
+
+    fn write_line(&mut self, mut line: &[u8]) -> datafusion::error::Result<()> {
+        while !line.is_empty() {
+            let read = self.decoder.decode(line)?;
+            line = &line[read..];
+            if self.decoder.capacity() == 0 {
+                self.flush()?;
+            }
+        }
+        Ok(())
+    }
+
+    fn flush(&mut self) -> datafusion::error::Result<()> {
+        if let Some(batch) = self.decoder.flush()? {
+            if self.writer.is_none() {
+                let props = WriterProperties::builder()
+                    .set_compression(Compression::SNAPPY)
+                    .build();
+                self.writer = Some(ArrowWriter::try_new(
+                    File::create(&self.filename)?,
+                    batch.schema(),
+                    Some(props),
+                )?);
+            }
+            self.writer.as_mut().unwrap().write(&batch)?;
+        }
+        Ok(())
+    }
+
+    fn close(mut self) -> datafusion::error::Result<()> {
+        self.flush()?;
+        if let Some(writer) = self.writer {
+            writer.close()?;
+        }
+        Ok(())
+    }
+}
+
+impl Default for TpcDs {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 #[async_trait]
 impl Tpc for TpcDs {
     fn generate(
         &self,
-        scale: usize,
+        scale: f64,
         partitions: usize,
         generator_path: &str,
         output_path: &str,
+        seed: Option<u64>,
+        progress: &Progress,
     ) -> Result<()> {
+        check_scale(scale)?;
+        // each partition is generated in its own working directory
+        let work_dirs = (0..partitions)
+            .map(|_| generator_work_dir(output_path))
+            .collect::<Result<Vec<_>>>()?;
+
         let mut handles = vec![];
 
         let start = Instant::now();
 
-        for i in 1..=partitions {
+        progress.report(ProgressEvent::GenerationStarted {
+            children: partitions,
+            expected_bytes: Some((RAW_BYTES_PER_SCALE_FACTOR * scale) as u64),
+        });
+        let watcher = progress.watch(output_path, &self.get_table_names());
+
+        for (i, work_dir) in (1..=partitions).zip(&work_dirs) {
             let generator_path = generator_path.to_owned();
+            let work_dir = work_dir.path().to_owned();
             let output_path = output_path.to_owned();
-            let scale = scale;
-            let partitions = partitions;
+            let progress = progress.clone();
+            let span = info_span!("partition", part = i);
             handles.push(thread::spawn(move || {
-                let output = Command::new("./dsdgen")
-                    .current_dir(generator_path.clone())
-                    .arg("-FORCE")
-                    .arg("-DIR")
-                    .arg(output_path)
-                    .arg("-SCALE")
-                    .arg(format!("{}", scale))
-                    .arg("-CHILD")
-                    .arg(format!("{}", i))
-                    .arg("-PARALLEL")
-                    .arg(format!("{}", partitions))
-                    .output();
-
-                match output {
-                    Ok(x) => println!("{:?}", x),
-                    Err(e) => println!(
-                        "Failed to build dsdgen command with path {}: {:?}",
-                        generator_path, e
-                    ),
-                }
+                let _span = span.entered();
+                info!("Generating partition {} of {}", i, partitions);
+                let result = dsdgen_command(&generator_path, &work_dir).and_then(|mut command| {
+                    command
+                        .arg("-FORCE")
+                        .arg("-DIR")
+                        .arg(&work_dir)
+                        .arg("-SCALE")
+                        .arg(format!("{}", scale))
+                        .arg("-CHILD")
+                        .arg(format!("{}", i))
+                        .arg("-PARALLEL")
+                        .arg(format!("{}", partitions))
+                        .args(seed_args(seed));
+                    run_logged(&mut command, &output_path, &format!("dsdgen-{}", i))
+                });
+                progress.report(ProgressEvent::ChildFinished { child: i });
+                result
             }));
         }
 
         // wait for all threads to finish
-        for h in handles {
-            h.join().unwrap();
-        }
+        join_generators(handles)?;
 
         let duration = start.elapsed();
 
-        println!(
-            "Generated TPC-DS data at scale factor {} with {} partitions in: {:?}",
-            scale, partitions, duration
+        info!(
+            scale,
+            partitions,
+            elapsed = ?duration,
+            "Generated TPC-DS data"
         );
 
         let tables = self.get_table_names();
@@ -88,18 +572,27 @@ impl Tpc for TpcDs {
         for table in &tables {
             let output_dir = format!("{}/{}.dat", output_path, table);
             if !Path::new(&output_dir).exists() {
-                println!("Creating directory {}", output_dir);
+                debug!(path = %output_dir, "Creating directory");
                 fs::create_dir(&output_dir)?;
             }
-            for i in 1..=partitions {
-                let filename = format!("{}/{}_{}_{}.dat", output_path, table, i, partitions);
+            for (i, work_dir) in (1..=partitions).zip(&work_dirs) {
+                let filename = format!(
+                    "{}/{}_{}_{}.dat",
+                    work_dir.path().display(),
+                    table,
+                    i,
+                    partitions
+                );
                 let filename2 = format!("{}/part-{}.dat", output_dir, i);
                 if Path::new(&filename).exists() {
-                    move_or_copy(&Path::new(&filename), &Path::new(&filename2))?;
+                    move_or_copy(Path::new(&filename), Path::new(&filename2))?;
                 }
             }
         }
 
+        drop(watcher);
+        progress.report(ProgressEvent::Finished);
+
         Ok(())
     }
 
@@ -132,8 +625,8 @@ impl Tpc for TpcDs {
         ]
     }
 
-    fn get_schema(&self, table: &str) -> Schema {
-        match table {
+    fn get_schema(&self, table: &str) -> Result<Schema> {
+        Ok(match table {
             "customer_address" => Schema::new(vec![
                 Field::new("ca_address_sk", DataType::Int32, false),
                 Field::new("ca_address_id", DataType::Utf8, false),
@@ -631,15 +1124,214 @@ impl Tpc for TpcDs {
                 Field::new("ss_net_profit", make_decimal_type(7, 2), true),
             ]),
 
-            _ => panic!(),
-        }
+            _ => return Err(unknown_table("TPC-DS", table)),
+        })
     }
 
     fn get_table_ext(&self) -> &str {
         "dat"
     }
+
+    fn get_primary_key(&self, table: &str) -> Result<Vec<&str>> {
+        Ok(match table {
+            "store_sales" => vec!["ss_item_sk", "ss_ticket_number"],
+            "store_returns" => vec!["sr_item_sk", "sr_ticket_number"],
+            "catalog_sales" => vec!["cs_item_sk", "cs_order_number"],
+            "catalog_returns" => vec!["cr_item_sk", "cr_order_number"],
+            "web_sales" => vec!["ws_item_sk", "ws_order_number"],
+            "web_returns" => vec!["wr_item_sk", "wr_order_number"],
+            "inventory" => vec!["inv_date_sk", "inv_item_sk", "inv_warehouse_sk"],
+            "call_center" => vec!["cc_call_center_sk"],
+            "catalog_page" => vec!["cp_catalog_page_sk"],
+            "customer" => vec!["c_customer_sk"],
+            "customer_address" => vec!["ca_address_sk"],
+            "customer_demographics" => vec!["cd_demo_sk"],
+            "date_dim" => vec!["d_date_sk"],
+            "household_demographics" => vec!["hd_demo_sk"],
+            "income_band" => vec!["ib_income_band_sk"],
+            "item" => vec!["i_item_sk"],
+            "promotion" => vec!["p_promo_sk"],
+            "reason" => vec!["r_reason_sk"],
+            "ship_mode" => vec!["sm_ship_mode_sk"],
+            "store" => vec!["s_store_sk"],
+            "time_dim" => vec!["t_time_sk"],
+            "warehouse" => vec!["w_warehouse_sk"],
+            "web_page" => vec!["wp_web_page_sk"],
+            "web_site" => vec!["web_site_sk"],
+            _ => return Err(unknown_table("TPC-DS", table)),
+        })
+    }
+
+    fn get_foreign_keys(&self, table: &str) -> Result<Vec<ForeignKey>> {
+        let mut keys = vec![];
+        for field in self.get_schema(table)?.fields() {
+            let column = field.name().as_str();
+            if let Some((_, referenced_table, referenced_column)) = SURROGATE_KEYS
+                .iter()
+                .find(|(suffix, _, _)| column.ends_with(suffix))
+            {
+                // the primary key of a dimension table matches its own suffix
+                if *referenced_table != table {
+                    keys.push(ForeignKey::new(
+                        &[column],
+                        referenced_table,
+                        &[referenced_column],
+                    ));
+                }
+            }
+        }
+        // returns refer to the sale that is being returned
+        let sale = match table {
+            "store_returns" => Some("store_sales"),
+            "catalog_returns" => Some("catalog_sales"),
+            "web_returns" => Some("web_sales"),
+            _ => None,
+        };
+        if let Some(sale) = sale {
+            keys.push(ForeignKey::new(
+                &self.get_primary_key(table)?,
+                sale,
+                &self.get_primary_key(sale)?,
+            ));
+        }
+        Ok(keys)
+    }
+
+    fn get_row_count(&self, table: &str, scale: f64) -> Option<u64> {
+        // these tables have the same size at every scale factor
+        match table {
+            "customer_demographics" => return Some(1_920_800),
+            "date_dim" => return Some(73_049),
+            "household_demographics" => return Some(7_200),
+            "income_band" => return Some(20),
+            "ship_mode" => return Some(20),
+            "time_dim" => return Some(86_400),
+            _ => {}
+        }
+        let i = SCALE_FACTORS.iter().position(|s| *s == scale)?;
+        ROW_COUNTS
+            .iter()
+            .find(|(name, _)| *name == table)
+            .map(|(_, counts)| counts[i])
+    }
+
+    fn get_table_type(&self, table: &str) -> Result<TableType> {
+        match table {
+            "store_sales" | "store_returns" | "catalog_sales" | "catalog_returns" | "web_sales"
+            | "web_returns" | "inventory" => Ok(TableType::Fact),
+            _ if self.get_table_names().contains(&table) => Ok(TableType::Dimension),
+            _ => Err(unknown_table("TPC-DS", table)),
+        }
+    }
 }
 
 fn make_decimal_type(p: u8, s: i8) -> DataType {
     DataType::Decimal128(p, s)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use datafusion::parquet::file::reader::{FileReader, SerializedFileReader};
+
+    #[test]
+    fn split_rows_with_ignore_column() -> datafusion::error::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let filename = dir.path().join("income_band.parquet");
+        let schema = with_ignore_column(TpcDs::new().get_schema("income_band").unwrap());
+        let output = TableOutput::new(Arc::new(schema), filename.display().to_string());
+        let mut outputs = HashMap::from([(4, output)]);
+
+        split_rows(
+            &b"1|0|10000|\n2|10001|20000|\n"[..],
+            "income_band",
+            &mut outputs,
+        )?;
+        outputs.remove(&4).unwrap().close()?;
+        let reader = SerializedFileReader::new(File::open(&filename)?)?;
+        let metadata = reader.metadata().file_metadata();
+        assert_eq!(2, metadata.num_rows());
+        let columns: Vec<&str> = metadata
+            .schema_descr()
+            .columns()
+            .iter()
+            .map(|c| c.name())
+            .collect();
+        assert_eq!(
+            vec![
+                "ib_income_band_sk",
+                "ib_lower_bound",
+                "ib_upper_bound",
+                "ignore"
+            ],
+            columns
+        );
+
+        let err = split_rows(&b"1|0|\n"[..], "income_band", &mut outputs).unwrap_err();
+        assert!(err.to_string().contains("Unexpected row"), "{}", err);
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn generate_fails_when_dsdgen_fails() -> Result<()> {
+        use std::os::unix::fs::PermissionsExt;
+
+        let generator_dir = tempfile::tempdir()?;
+        let dsdgen = generator_dir.path().join("dsdgen");
+        fs::write(
+            &dsdgen,
+            "#!/bin/sh\necho 'cannot open tpcds.idx' >&2\nexit 1\n",
+        )?;
+        fs::set_permissions(&dsdgen, fs::Permissions::from_mode(0o755))?;
+        let output_dir = tempfile::tempdir()?;
+
+        let err = TpcDs::new()
+            .generate(
+                1.0,
+                2,
+                generator_dir.path().to_str().unwrap(),
+                output_dir.path().to_str().unwrap(),
+                None,
+                &Progress::none(),
+            )
+            .unwrap_err();
+        let log_path = output_dir.path().join("logs").join("dsdgen-1.log");
+        assert!(
+            err.to_string().contains(&log_path.display().to_string()),
+            "{}",
+            err
+        );
+        assert_eq!("cannot open tpcds.idx\n", fs::read_to_string(log_path)?);
+        Ok(())
+    }
+
+    #[test]
+    fn row_counts_for_spec_scale_factors() {
+        let tpcds = TpcDs::new();
+        assert_eq!(Some(2_880_404), tpcds.get_row_count("store_sales", 1.0));
+        assert_eq!(Some(287_997_024), tpcds.get_row_count("store_sales", 100.0));
+        assert_eq!(Some(502_000), tpcds.get_row_count("item", 100000.0));
+        assert_eq!(Some(73_049), tpcds.get_row_count("date_dim", 3000.0));
+        assert_eq!(None, tpcds.get_row_count("store_sales", 2.0));
+        for table in tpcds.get_table_names() {
+            assert!(tpcds.get_row_count(table, 10.0).is_some(), "{}", table);
+        }
+    }
+
+    #[test]
+    fn table_types() {
+        let tpcds = TpcDs::new();
+        assert_eq!(
+            TableType::Fact,
+            tpcds.get_table_type("store_sales").unwrap()
+        );
+        assert_eq!(
+            TableType::Dimension,
+            tpcds.get_table_type("date_dim").unwrap()
+        );
+        let err = tpcds.get_table_type("lineitem").unwrap_err();
+        assert_eq!(std::io::ErrorKind::InvalidInput, err.kind());
+        assert_eq!("Unknown TPC-DS table 'lineitem'", err.to_string());
+    }
+}
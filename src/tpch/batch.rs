@@ -0,0 +1,304 @@
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Conversion of the generated rows to Arrow record batches, using the schemas returned by
+//! `TpcH::get_schema`. The trailing `ignore` column of each schema is always null.
+
+use std::sync::Arc;
+
+use datafusion::arrow::array::{
+    new_null_array, ArrayRef, Date32Array, Decimal128Array, Int32Array, Int64Array, StringArray,
+};
+use datafusion::arrow::datatypes::{DataType, SchemaRef};
+use datafusion::arrow::error::{ArrowError, Result};
+use datafusion::arrow::record_batch::RecordBatch;
+
+use super::generator::*;
+use super::TpcH;
+use crate::Tpc;
+
+/// Iterator over the record batches for one chunk of a table
+pub struct RecordBatchIterator<I: Iterator> {
+    rows: I,
+    schema: SchemaRef,
+    batch_size: usize,
+    to_columns: fn(&[I::Item]) -> Result<Vec<ArrayRef>>,
+}
+
+impl<I: Iterator> RecordBatchIterator<I> {
+    fn new(
+        schema: SchemaRef,
+        rows: I,
+        batch_size: usize,
+        to_columns: fn(&[I::Item]) -> Result<Vec<ArrayRef>>,
+    ) -> Self {
+        Self {
+            rows,
+            schema,
+            batch_size,
+            to_columns,
+        }
+    }
+}
+
+impl<I: Iterator> Iterator for RecordBatchIterator<I> {
+    type Item = Result<RecordBatch>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let rows: Vec<I::Item> = self.rows.by_ref().take(self.batch_size).collect();
+        if rows.is_empty() {
+            return None;
+        }
+        Some((self.to_columns)(&rows).and_then(|mut columns| {
+            columns.push(new_null_array(&DataType::Utf8, rows.len()));
+            RecordBatch::try_new(self.schema.clone(), columns)
+        }))
+    }
+}
+
+/// Generate one chunk of a table as record batches of up to `batch_size` rows, without writing
+/// any `.tbl` files. Parts are numbered from 1, and the nation and region tables are only
+/// generated for the first part. See `TpcH::generate_native` for the `seed`.
+pub fn record_batches(
+    table: &str,
+    scale: f64,
+    part: usize,
+    part_count: usize,
+    seed: Option<u64>,
+    batch_size: usize,
+) -> Result<Box<dyn Iterator<Item = Result<RecordBatch>> + Send>> {
+    let schema = TpcH::new()
+        .get_schema(table)
+        .map_err(|e| ArrowError::InvalidArgumentError(e.to_string()))?;
+    let schema = Arc::new(schema);
+    Ok(match table {
+        "part" => Box::new(RecordBatchIterator::new(
+            schema,
+            PartGenerator::new(scale, part, part_count, seed),
+            batch_size,
+            part_columns,
+        )),
+        "supplier" => Box::new(RecordBatchIterator::new(
+            schema,
+            SupplierGenerator::new(scale, part, part_count, seed),
+            batch_size,
+            supplier_columns,
+        )),
+        "partsupp" => Box::new(RecordBatchIterator::new(
+            schema,
+            PartSuppGenerator::new(scale, part, part_count, seed),
+            batch_size,
+            part_supp_columns,
+        )),
+        "customer" => Box::new(RecordBatchIterator::new(
+            schema,
+            CustomerGenerator::new(scale, part, part_count, seed),
+            batch_size,
+            customer_columns,
+        )),
+        "orders" => Box::new(RecordBatchIterator::new(
+            schema,
+            OrderGenerator::new(scale, part, part_count, seed),
+            batch_size,
+            order_columns,
+        )),
+        "lineitem" => Box::new(RecordBatchIterator::new(
+            schema,
+            LineItemGenerator::new(scale, part, part_count, seed),
+            batch_size,
+            line_item_columns,
+        )),
+        "nation" => Box::new(RecordBatchIterator::new(
+            schema,
+            NationGenerator::new().take(if part == 1 { usize::MAX } else { 0 }),
+            batch_size,
+            nation_columns,
+        )),
+        "region" => Box::new(RecordBatchIterator::new(
+            schema,
+            RegionGenerator::new().take(if part == 1 { usize::MAX } else { 0 }),
+            batch_size,
+            region_columns,
+        )),
+        _ => {
+            return Err(ArrowError::InvalidArgumentError(format!(
+                "Unknown TPC-H table '{}'",
+                table
+            )))
+        }
+    })
+}
+
+fn int64<T>(rows: &[T], f: impl Fn(&T) -> i64) -> ArrayRef {
+    Arc::new(Int64Array::from_iter_values(rows.iter().map(f)))
+}
+
+fn int32<T>(rows: &[T], f: impl Fn(&T) -> i32) -> ArrayRef {
+    Arc::new(Int32Array::from_iter_values(rows.iter().map(f)))
+}
+
+fn string<'a, T, S: AsRef<str>>(rows: &'a [T], f: impl Fn(&'a T) -> S) -> ArrayRef {
+    Arc::new(rows.iter().map(|row| Some(f(row))).collect::<StringArray>())
+}
+
+fn date<T>(rows: &[T], f: impl Fn(&T) -> i32) -> ArrayRef {
+    Arc::new(Date32Array::from_iter_values(rows.iter().map(f)))
+}
+
+/// Money and other decimal values are generated in hundredths, which matches the scale of the
+/// `Decimal128(11, 2)` columns
+fn decimal<T>(rows: &[T], f: impl Fn(&T) -> i64) -> Result<ArrayRef> {
+    let array = Decimal128Array::from_iter_values(rows.iter().map(|row| f(row) as i128))
+        .with_precision_and_scale(11, 2)?;
+    Ok(Arc::new(array))
+}
+
+fn part_columns(rows: &[Part]) -> Result<Vec<ArrayRef>> {
+    Ok(vec![
+        int64(rows, |r| r.part_key),
+        string(rows, |r| r.name.as_str()),
+        string(rows, |r| format!("Manufacturer#{}", r.manufacturer)),
+        string(rows, |r| format!("Brand#{}", r.brand)),
+        string(rows, |r| r.part_type),
+        int32(rows, |r| r.size),
+        string(rows, |r| r.container),
+        decimal(rows, |r| r.retail_price)?,
+        string(rows, |r| r.comment),
+    ])
+}
+
+fn supplier_columns(rows: &[Supplier]) -> Result<Vec<ArrayRef>> {
+    Ok(vec![
+        int64(rows, |r| r.supplier_key),
+        string(rows, |r| format!("Supplier#{:09}", r.supplier_key)),
+        string(rows, |r| r.address.as_str()),
+        int64(rows, |r| r.nation_key),
+        string(rows, |r| r.phone.as_str()),
+        decimal(rows, |r| r.account_balance)?,
+        string(rows, |r| r.comment.as_str()),
+    ])
+}
+
+fn part_supp_columns(rows: &[PartSupp]) -> Result<Vec<ArrayRef>> {
+    Ok(vec![
+        int64(rows, |r| r.part_key),
+        int64(rows, |r| r.supplier_key),
+        int32(rows, |r| r.available_quantity),
+        decimal(rows, |r| r.supply_cost)?,
+        string(rows, |r| r.comment),
+    ])
+}
+
+fn customer_columns(rows: &[Customer]) -> Result<Vec<ArrayRef>> {
+    Ok(vec![
+        int64(rows, |r| r.customer_key),
+        string(rows, |r| format!("Customer#{:09}", r.customer_key)),
+        string(rows, |r| r.address.as_str()),
+        int64(rows, |r| r.nation_key),
+        string(rows, |r| r.phone.as_str()),
+        decimal(rows, |r| r.account_balance)?,
+        string(rows, |r| r.market_segment),
+        string(rows, |r| r.comment),
+    ])
+}
+
+fn order_columns(rows: &[Order]) -> Result<Vec<ArrayRef>> {
+    Ok(vec![
+        int64(rows, |r| r.order_key),
+        int64(rows, |r| r.customer_key),
+        string(rows, |r| r.order_status.to_string()),
+        decimal(rows, |r| r.total_price)?,
+        date(rows, |r| r.order_date),
+        string(rows, |r| r.order_priority),
+        string(rows, |r| format!("Clerk#{:09}", r.clerk)),
+        int32(rows, |r| r.ship_priority),
+        string(rows, |r| r.comment),
+    ])
+}
+
+fn line_item_columns(rows: &[LineItem]) -> Result<Vec<ArrayRef>> {
+    Ok(vec![
+        int64(rows, |r| r.order_key),
+        int64(rows, |r| r.part_key),
+        int64(rows, |r| r.supplier_key),
+        int32(rows, |r| r.line_number),
+        decimal(rows, |r| r.quantity * 100)?,
+        decimal(rows, |r| r.extended_price)?,
+        decimal(rows, |r| r.discount)?,
+        decimal(rows, |r| r.tax)?,
+        string(rows, |r| r.return_flag),
+        string(rows, |r| r.line_status.to_string()),
+        date(rows, |r| r.ship_date),
+        date(rows, |r| r.commit_date),
+        date(rows, |r| r.receipt_date),
+        string(rows, |r| r.ship_instructions),
+        string(rows, |r| r.ship_mode),
+        string(rows, |r| r.comment),
+    ])
+}
+
+fn nation_columns(rows: &[Nation]) -> Result<Vec<ArrayRef>> {
+    Ok(vec![
+        int64(rows, |r| r.nation_key),
+        string(rows, |r| r.name),
+        int64(rows, |r| r.region_key),
+        string(rows, |r| r.comment),
+    ])
+}
+
+fn region_columns(rows: &[Region]) -> Result<Vec<ArrayRef>> {
+    Ok(vec![
+        int64(rows, |r| r.region_key),
+        string(rows, |r| r.name),
+        string(rows, |r| r.comment),
+    ])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use datafusion::arrow::array::Array;
+
+    #[test]
+    fn nation_batches() -> Result<()> {
+        let batches = record_batches("nation", 1.0, 1, 1, None, 10)?.collect::<Result<Vec<_>>>()?;
+        assert_eq!(
+            vec![10, 10, 5],
+            batches.iter().map(|b| b.num_rows()).collect::<Vec<_>>()
+        );
+        assert_eq!(
+            Arc::new(TpcH::new().get_schema("nation").unwrap()),
+            batches[0].schema()
+        );
+        let comments = batches[0]
+            .column(3)
+            .as_any()
+            .downcast_ref::<StringArray>()
+            .unwrap();
+        assert_eq!(
+            " haggle. carefully final deposits detect slyly agai",
+            comments.value(0)
+        );
+        assert_eq!(10, batches[0].column(4).null_count());
+        // the small tables are only generated for the first part
+        assert_eq!(0, record_batches("nation", 1.0, 2, 2, None, 10)?.count());
+        Ok(())
+    }
+
+    #[test]
+    fn record_batches_rejects_unknown_table() {
+        assert!(matches!(
+            record_batches("nations", 1.0, 1, 1, None, 10),
+            Err(ArrowError::InvalidArgumentError(_))
+        ));
+    }
+}
@@ -0,0 +1,1106 @@
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Row generators for each TPC-H table. Each generator produces one chunk of a table, which is
+//! equivalent to running `dbgen -C <part_count> -S <part>`, and formats rows the same way as the
+//! `.tbl` files written by dbgen.
+
+use std::fmt;
+
+use super::distribution::*;
+use super::random::*;
+use super::text::TextPool;
+
+/// First date that is generated, in the `YYDDD` form used by dbgen (1992-01-01)
+const MIN_GENERATE_DATE: i32 = 92001;
+/// Number of days between 1970-01-01 and 1992-01-01
+const EPOCH_DAYS_1992: i32 = 8035;
+/// Number of days in the generated date range (1992-01-01 to 1998-12-31)
+const TOTAL_DATE_RANGE: i32 = 2557;
+/// Offset of the current date (1995-06-17) from the first date, used to decide whether an
+/// order has shipped or been returned
+const CURRENT_DATE_OFFSET: i32 = 1263;
+
+const SHIP_DATE_MIN: i32 = 1;
+const SHIP_DATE_MAX: i32 = 121;
+const COMMIT_DATE_MIN: i32 = 30;
+const COMMIT_DATE_MAX: i32 = 90;
+const RECEIPT_DATE_MIN: i32 = 1;
+const RECEIPT_DATE_MAX: i32 = 30;
+
+const ORDER_DATE_MIN: i32 = MIN_GENERATE_DATE;
+const ORDER_DATE_MAX: i32 =
+    MIN_GENERATE_DATE + (TOTAL_DATE_RANGE - (SHIP_DATE_MAX + RECEIPT_DATE_MAX) - 1);
+
+const PART_SCALE_BASE: i64 = 200_000;
+const SUPPLIER_SCALE_BASE: i64 = 10_000;
+const CUSTOMER_SCALE_BASE: i64 = 150_000;
+const ORDER_SCALE_BASE: i64 = 1_500_000;
+
+const SUPPLIERS_PER_PART: i64 = 4;
+const LINE_COUNT_MAX: i32 = 7;
+const CUSTOMER_MORTALITY: i64 = 3;
+const CLERK_SCALE_BASE: i32 = 1000;
+
+/// Scale factor at which keys no longer fit in the 32 bit random streams
+const USE_64_BIT_SCALE: f64 = 30000.0;
+
+/// Range of rows in a chunk of a table, with any remainder going to the last chunk
+fn chunk(base: i64, scale: f64, part: usize, part_count: usize) -> (i64, i64) {
+    let total = (base as f64 * scale) as i64;
+    let rows_per_part = total / part_count as i64;
+    let start = rows_per_part * (part as i64 - 1);
+    let count = if part == part_count {
+        rows_per_part + total % part_count as i64
+    } else {
+        rows_per_part
+    };
+    (start, count)
+}
+
+/// Convert a date generated in the `YYDDD` range to the number of days since 1970-01-01
+fn to_epoch_date(date: i32) -> i32 {
+    date - MIN_GENERATE_DATE + EPOCH_DAYS_1992
+}
+
+fn is_in_past(date: i32) -> bool {
+    date - MIN_GENERATE_DATE <= CURRENT_DATE_OFFSET
+}
+
+/// Format a number of days since 1970-01-01 as `YYYY-MM-DD`
+pub(crate) fn format_date(days: i32) -> String {
+    // see http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+/// Format an amount in cents with two decimal places
+pub(crate) fn format_money(cents: i64) -> String {
+    let sign = if cents < 0 { "-" } else { "" };
+    format!("{}{}.{:02}", sign, cents.abs() / 100, cents.abs() % 100)
+}
+
+/// Retail price of a part in cents, which is derived from the part key
+fn part_price(part_key: i64) -> i64 {
+    90000 + (part_key / 10) % 20001 + (part_key % 1000) * 100
+}
+
+/// Supplier key of one of the four suppliers of a part
+fn part_supplier(part_key: i64, supplier_number: i64, scale: f64) -> i64 {
+    let supplier_count = (SUPPLIER_SCALE_BASE as f64 * scale) as i64;
+    (part_key
+        + supplier_number * (supplier_count / SUPPLIERS_PER_PART + (part_key - 1) / supplier_count))
+        % supplier_count
+        + 1
+}
+
+/// Order keys are sparse, with only the first 8 of every 32 keys used
+fn order_key(order_index: i64) -> i64 {
+    let low_bits = order_index & 7;
+    ((order_index >> 3) << 5) + low_bits
+}
+
+#[derive(Debug, Clone)]
+pub struct Part {
+    pub part_key: i64,
+    pub name: String,
+    pub manufacturer: i32,
+    pub brand: i32,
+    pub part_type: &'static str,
+    pub size: i32,
+    pub container: &'static str,
+    pub retail_price: i64,
+    pub comment: &'static str,
+}
+
+impl fmt::Display for Part {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}|{}|Manufacturer#{}|Brand#{}|{}|{}|{}|{}|{}|",
+            self.part_key,
+            self.name,
+            self.manufacturer,
+            self.brand,
+            self.part_type,
+            self.size,
+            self.container,
+            format_money(self.retail_price),
+            self.comment
+        )
+    }
+}
+
+pub struct PartGenerator {
+    index: i64,
+    end: i64,
+    name: RandomStringSequence,
+    manufacturer: RandomBoundedInt,
+    brand: RandomBoundedInt,
+    part_type: RandomString,
+    size: RandomBoundedInt,
+    container: RandomString,
+    comment: RandomText,
+}
+
+impl PartGenerator {
+    pub fn new(scale: f64, part: usize, part_count: usize, seed: Option<u64>) -> Self {
+        let (start, count) = chunk(PART_SCALE_BASE, scale, part, part_count);
+        let mut generator = Self {
+            index: start,
+            end: start + count,
+            name: RandomStringSequence::new(
+                stream_seed(709314158, seed),
+                5,
+                Distribution::new(COLORS),
+            ),
+            manufacturer: RandomBoundedInt::new(stream_seed(1, seed), 1, 5, 1),
+            brand: RandomBoundedInt::new(stream_seed(46831694, seed), 1, 5, 1),
+            part_type: RandomString::new(
+                stream_seed(1841581359, seed),
+                Distribution::new(&part_types()),
+                1,
+            ),
+            size: RandomBoundedInt::new(stream_seed(1193163244, seed), 1, 50, 1),
+            container: RandomString::new(
+                stream_seed(727633698, seed),
+                Distribution::new(&part_containers()),
+                1,
+            ),
+            comment: RandomText::new(stream_seed(804159733, seed), TextPool::get(), 14.0, 1),
+        };
+        generator.name.advance_rows(start);
+        generator.manufacturer.advance_rows(start);
+        generator.brand.advance_rows(start);
+        generator.part_type.advance_rows(start);
+        generator.size.advance_rows(start);
+        generator.container.advance_rows(start);
+        generator.comment.advance_rows(start);
+        generator
+    }
+}
+
+impl Iterator for PartGenerator {
+    type Item = Part;
+
+    fn next(&mut self) -> Option<Part> {
+        if self.index >= self.end {
+            return None;
+        }
+        let part_key = self.index + 1;
+        let manufacturer = self.manufacturer.next_value();
+        let part = Part {
+            part_key,
+            name: self.name.next_value(),
+            manufacturer,
+            brand: manufacturer * 10 + self.brand.next_value(),
+            part_type: self.part_type.next_value(),
+            size: self.size.next_value(),
+            container: self.container.next_value(),
+            retail_price: part_price(part_key),
+            comment: self.comment.next_value(),
+        };
+        self.name.row_finished();
+        self.manufacturer.row_finished();
+        self.brand.row_finished();
+        self.part_type.row_finished();
+        self.size.row_finished();
+        self.container.row_finished();
+        self.comment.row_finished();
+        self.index += 1;
+        Some(part)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Supplier {
+    pub supplier_key: i64,
+    pub address: String,
+    pub nation_key: i64,
+    pub phone: String,
+    pub account_balance: i64,
+    pub comment: String,
+}
+
+impl fmt::Display for Supplier {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}|Supplier#{:09}|{}|{}|{}|{}|{}|",
+            self.supplier_key,
+            self.supplier_key,
+            self.address,
+            self.nation_key,
+            self.phone,
+            format_money(self.account_balance),
+            self.comment
+        )
+    }
+}
+
+const BBB_BASE_TEXT: &str = "Customer ";
+const BBB_COMPLAINT_TEXT: &str = "Complaints";
+const BBB_RECOMMEND_TEXT: &str = "Recommends";
+const BBB_COMMENT_LENGTH: i32 = 19;
+const BBB_COMMENTS_PER_SCALE_BASE: i32 = 10;
+const BBB_COMPLAINT_PERCENT: i32 = 50;
+
+pub struct SupplierGenerator {
+    index: i64,
+    end: i64,
+    address: RandomAlphaNumeric,
+    nation_key: RandomBoundedInt,
+    phone: RandomPhoneNumber,
+    account_balance: RandomBoundedInt,
+    comment: RandomText,
+    bbb_comment: RandomBoundedInt,
+    bbb_junk: RandomInt,
+    bbb_offset: RandomInt,
+    bbb_type: RandomBoundedInt,
+}
+
+impl SupplierGenerator {
+    pub fn new(scale: f64, part: usize, part_count: usize, seed: Option<u64>) -> Self {
+        let (start, count) = chunk(SUPPLIER_SCALE_BASE, scale, part, part_count);
+        let mut generator = Self {
+            index: start,
+            end: start + count,
+            address: RandomAlphaNumeric::new(stream_seed(706178559, seed), 25.0),
+            nation_key: RandomBoundedInt::new(
+                stream_seed(110356601, seed),
+                0,
+                NATIONS.len() as i32 - 1,
+                1,
+            ),
+            phone: RandomPhoneNumber::new(stream_seed(884434366, seed)),
+            account_balance: RandomBoundedInt::new(stream_seed(962338209, seed), -99999, 999999, 1),
+            comment: RandomText::new(stream_seed(1341315363, seed), TextPool::get(), 63.0, 1),
+            bbb_comment: RandomBoundedInt::new(
+                stream_seed(202794285, seed),
+                1,
+                SUPPLIER_SCALE_BASE as i32,
+                1,
+            ),
+            bbb_junk: RandomInt::new(stream_seed(263032577, seed), 1),
+            bbb_offset: RandomInt::new(stream_seed(715851524, seed), 1),
+            bbb_type: RandomBoundedInt::new(stream_seed(753643799, seed), 0, 100, 1),
+        };
+        generator.address.advance_rows(start);
+        generator.nation_key.advance_rows(start);
+        generator.phone.advance_rows(start);
+        generator.account_balance.advance_rows(start);
+        generator.comment.advance_rows(start);
+        generator.bbb_comment.advance_rows(start);
+        generator.bbb_junk.advance_rows(start);
+        generator.bbb_offset.advance_rows(start);
+        generator.bbb_type.advance_rows(start);
+        generator
+    }
+
+    /// Some supplier comments contain Better Business Bureau complaints or recommendations,
+    /// which are written over a random part of the comment text
+    fn comment(&mut self) -> String {
+        let mut comment = self.comment.next_value().to_owned();
+        if self.bbb_comment.next_value() <= BBB_COMMENTS_PER_SCALE_BASE {
+            let length = comment.len() as i32;
+            let noise = self.bbb_junk.next_int(0, length - BBB_COMMENT_LENGTH);
+            let offset = self
+                .bbb_offset
+                .next_int(0, length - (BBB_COMMENT_LENGTH + noise));
+            let text = if self.bbb_type.next_value() < BBB_COMPLAINT_PERCENT {
+                BBB_COMPLAINT_TEXT
+            } else {
+                BBB_RECOMMEND_TEXT
+            };
+            let offset = offset as usize;
+            let type_offset = offset + BBB_BASE_TEXT.len() + noise as usize;
+            comment.replace_range(offset..offset + BBB_BASE_TEXT.len(), BBB_BASE_TEXT);
+            comment.replace_range(type_offset..type_offset + text.len(), text);
+        }
+        comment
+    }
+}
+
+impl Iterator for SupplierGenerator {
+    type Item = Supplier;
+
+    fn next(&mut self) -> Option<Supplier> {
+        if self.index >= self.end {
+            return None;
+        }
+        let comment = self.comment();
+        let nation_key = self.nation_key.next_value() as i64;
+        let supplier = Supplier {
+            supplier_key: self.index + 1,
+            address: self.address.next_value(),
+            nation_key,
+            phone: self.phone.next_value(nation_key),
+            account_balance: self.account_balance.next_value() as i64,
+            comment,
+        };
+        self.address.row_finished();
+        self.nation_key.row_finished();
+        self.phone.row_finished();
+        self.account_balance.row_finished();
+        self.comment.row_finished();
+        self.bbb_comment.row_finished();
+        self.bbb_junk.row_finished();
+        self.bbb_offset.row_finished();
+        self.bbb_type.row_finished();
+        self.index += 1;
+        Some(supplier)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct PartSupp {
+    pub part_key: i64,
+    pub supplier_key: i64,
+    pub available_quantity: i32,
+    pub supply_cost: i64,
+    pub comment: &'static str,
+}
+
+impl fmt::Display for PartSupp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}|{}|{}|{}|{}|",
+            self.part_key,
+            self.supplier_key,
+            self.available_quantity,
+            format_money(self.supply_cost),
+            self.comment
+        )
+    }
+}
+
+/// Generates the four suppliers of each part, so chunks are in terms of parts
+pub struct PartSuppGenerator {
+    scale: f64,
+    index: i64,
+    end: i64,
+    supplier_number: i64,
+    available_quantity: RandomBoundedInt,
+    supply_cost: RandomBoundedInt,
+    comment: RandomText,
+}
+
+impl PartSuppGenerator {
+    pub fn new(scale: f64, part: usize, part_count: usize, seed: Option<u64>) -> Self {
+        let (start, count) = chunk(PART_SCALE_BASE, scale, part, part_count);
+        let suppliers = SUPPLIERS_PER_PART as i32;
+        let mut generator = Self {
+            scale,
+            index: start,
+            end: start + count,
+            supplier_number: 0,
+            available_quantity: RandomBoundedInt::new(
+                stream_seed(1671059989, seed),
+                1,
+                9999,
+                suppliers,
+            ),
+            supply_cost: RandomBoundedInt::new(
+                stream_seed(1051288424, seed),
+                100,
+                100000,
+                suppliers,
+            ),
+            comment: RandomText::new(
+                stream_seed(1961692154, seed),
+                TextPool::get(),
+                124.0,
+                suppliers,
+            ),
+        };
+        generator.available_quantity.advance_rows(start);
+        generator.supply_cost.advance_rows(start);
+        generator.comment.advance_rows(start);
+        generator
+    }
+}
+
+impl Iterator for PartSuppGenerator {
+    type Item = PartSupp;
+
+    fn next(&mut self) -> Option<PartSupp> {
+        if self.index >= self.end {
+            return None;
+        }
+        let part_key = self.index + 1;
+        let part_supp = PartSupp {
+            part_key,
+            supplier_key: part_supplier(part_key, self.supplier_number, self.scale),
+            available_quantity: self.available_quantity.next_value(),
+            supply_cost: self.supply_cost.next_value() as i64,
+            comment: self.comment.next_value(),
+        };
+        self.supplier_number += 1;
+        if self.supplier_number >= SUPPLIERS_PER_PART {
+            self.available_quantity.row_finished();
+            self.supply_cost.row_finished();
+            self.comment.row_finished();
+            self.supplier_number = 0;
+            self.index += 1;
+        }
+        Some(part_supp)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Customer {
+    pub customer_key: i64,
+    pub address: String,
+    pub nation_key: i64,
+    pub phone: String,
+    pub account_balance: i64,
+    pub market_segment: &'static str,
+    pub comment: &'static str,
+}
+
+impl fmt::Display for Customer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}|Customer#{:09}|{}|{}|{}|{}|{}|{}|",
+            self.customer_key,
+            self.customer_key,
+            self.address,
+            self.nation_key,
+            self.phone,
+            format_money(self.account_balance),
+            self.market_segment,
+            self.comment
+        )
+    }
+}
+
+pub struct CustomerGenerator {
+    index: i64,
+    end: i64,
+    address: RandomAlphaNumeric,
+    nation_key: RandomBoundedInt,
+    phone: RandomPhoneNumber,
+    account_balance: RandomBoundedInt,
+    market_segment: RandomString,
+    comment: RandomText,
+}
+
+impl CustomerGenerator {
+    pub fn new(scale: f64, part: usize, part_count: usize, seed: Option<u64>) -> Self {
+        let (start, count) = chunk(CUSTOMER_SCALE_BASE, scale, part, part_count);
+        let mut generator = Self {
+            index: start,
+            end: start + count,
+            address: RandomAlphaNumeric::new(stream_seed(881155353, seed), 25.0),
+            nation_key: RandomBoundedInt::new(
+                stream_seed(1489529863, seed),
+                0,
+                NATIONS.len() as i32 - 1,
+                1,
+            ),
+            phone: RandomPhoneNumber::new(stream_seed(1521138112, seed)),
+            account_balance: RandomBoundedInt::new(stream_seed(298370230, seed), -99999, 999999, 1),
+            market_segment: RandomString::new(
+                stream_seed(1140279430, seed),
+                Distribution::new(MARKET_SEGMENTS),
+                1,
+            ),
+            comment: RandomText::new(stream_seed(1335826707, seed), TextPool::get(), 73.0, 1),
+        };
+        generator.address.advance_rows(start);
+        generator.nation_key.advance_rows(start);
+        generator.phone.advance_rows(start);
+        generator.account_balance.advance_rows(start);
+        generator.market_segment.advance_rows(start);
+        generator.comment.advance_rows(start);
+        generator
+    }
+}
+
+impl Iterator for CustomerGenerator {
+    type Item = Customer;
+
+    fn next(&mut self) -> Option<Customer> {
+        if self.index >= self.end {
+            return None;
+        }
+        let nation_key = self.nation_key.next_value() as i64;
+        let customer = Customer {
+            customer_key: self.index + 1,
+            address: self.address.next_value(),
+            nation_key,
+            phone: self.phone.next_value(nation_key),
+            account_balance: self.account_balance.next_value() as i64,
+            market_segment: self.market_segment.next_value(),
+            comment: self.comment.next_value(),
+        };
+        self.address.row_finished();
+        self.nation_key.row_finished();
+        self.phone.row_finished();
+        self.account_balance.row_finished();
+        self.market_segment.row_finished();
+        self.comment.row_finished();
+        self.index += 1;
+        Some(customer)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Order {
+    pub order_key: i64,
+    pub customer_key: i64,
+    pub order_status: char,
+    pub total_price: i64,
+    /// Days since 1970-01-01
+    pub order_date: i32,
+    pub order_priority: &'static str,
+    pub clerk: i32,
+    pub ship_priority: i32,
+    pub comment: &'static str,
+}
+
+impl fmt::Display for Order {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}|{}|{}|{}|{}|{}|Clerk#{:09}|{}|{}|",
+            self.order_key,
+            self.customer_key,
+            self.order_status,
+            format_money(self.total_price),
+            format_date(self.order_date),
+            self.order_priority,
+            self.clerk,
+            self.ship_priority,
+            self.comment
+        )
+    }
+}
+
+fn order_date_random(seed: Option<u64>) -> RandomBoundedInt {
+    RandomBoundedInt::new(
+        stream_seed(1066728069, seed),
+        ORDER_DATE_MIN,
+        ORDER_DATE_MAX,
+        1,
+    )
+}
+
+fn line_count_random(seed: Option<u64>) -> RandomBoundedInt {
+    RandomBoundedInt::new(stream_seed(1434868289, seed), 1, LINE_COUNT_MAX, 1)
+}
+
+fn quantity_random(seed: Option<u64>) -> RandomBoundedInt {
+    RandomBoundedInt::new(stream_seed(209208115, seed), 1, 50, LINE_COUNT_MAX)
+}
+
+fn discount_random(seed: Option<u64>) -> RandomBoundedInt {
+    RandomBoundedInt::new(stream_seed(554590007, seed), 0, 10, LINE_COUNT_MAX)
+}
+
+fn tax_random(seed: Option<u64>) -> RandomBoundedInt {
+    RandomBoundedInt::new(stream_seed(721958466, seed), 0, 8, LINE_COUNT_MAX)
+}
+
+fn part_key_random(scale: f64, seed: Option<u64>) -> RandomBoundedLong {
+    RandomBoundedLong::new(
+        stream_seed(1808217256, seed),
+        scale >= USE_64_BIT_SCALE,
+        1,
+        (PART_SCALE_BASE as f64 * scale) as i64,
+        LINE_COUNT_MAX,
+    )
+}
+
+fn ship_date_random(seed: Option<u64>) -> RandomBoundedInt {
+    RandomBoundedInt::new(
+        stream_seed(1769349045, seed),
+        SHIP_DATE_MIN,
+        SHIP_DATE_MAX,
+        LINE_COUNT_MAX,
+    )
+}
+
+pub struct OrderGenerator {
+    index: i64,
+    end: i64,
+    max_customer_key: i64,
+    order_date: RandomBoundedInt,
+    line_count: RandomBoundedInt,
+    customer_key: RandomBoundedLong,
+    order_priority: RandomString,
+    clerk: RandomBoundedInt,
+    comment: RandomText,
+    // the line item streams are needed to calculate the total price and order status
+    line_quantity: RandomBoundedInt,
+    line_discount: RandomBoundedInt,
+    line_tax: RandomBoundedInt,
+    line_part_key: RandomBoundedLong,
+    line_ship_date: RandomBoundedInt,
+}
+
+impl OrderGenerator {
+    pub fn new(scale: f64, part: usize, part_count: usize, seed: Option<u64>) -> Self {
+        let (start, count) = chunk(ORDER_SCALE_BASE, scale, part, part_count);
+        let max_customer_key = (CUSTOMER_SCALE_BASE as f64 * scale) as i64;
+        let clerks = ((scale * CLERK_SCALE_BASE as f64) as i32).max(CLERK_SCALE_BASE);
+        let mut generator = Self {
+            index: start,
+            end: start + count,
+            max_customer_key,
+            order_date: order_date_random(seed),
+            line_count: line_count_random(seed),
+            customer_key: RandomBoundedLong::new(
+                stream_seed(851767375, seed),
+                scale >= USE_64_BIT_SCALE,
+                1,
+                max_customer_key,
+                1,
+            ),
+            order_priority: RandomString::new(
+                stream_seed(591449447, seed),
+                Distribution::new(ORDER_PRIORITIES),
+                1,
+            ),
+            clerk: RandomBoundedInt::new(stream_seed(1171034773, seed), 1, clerks, 1),
+            comment: RandomText::new(stream_seed(276090261, seed), TextPool::get(), 49.0, 1),
+            line_quantity: quantity_random(seed),
+            line_discount: discount_random(seed),
+            line_tax: tax_random(seed),
+            line_part_key: part_key_random(scale, seed),
+            line_ship_date: ship_date_random(seed),
+        };
+        generator.order_date.advance_rows(start);
+        generator.line_count.advance_rows(start);
+        generator.customer_key.advance_rows(start);
+        generator.order_priority.advance_rows(start);
+        generator.clerk.advance_rows(start);
+        generator.comment.advance_rows(start);
+        generator.line_quantity.advance_rows(start);
+        generator.line_discount.advance_rows(start);
+        generator.line_tax.advance_rows(start);
+        generator.line_part_key.advance_rows(start);
+        generator.line_ship_date.advance_rows(start);
+        generator
+    }
+}
+
+impl Iterator for OrderGenerator {
+    type Item = Order;
+
+    fn next(&mut self) -> Option<Order> {
+        if self.index >= self.end {
+            return None;
+        }
+        let order_date = self.order_date.next_value();
+
+        // a third of the customers have no orders
+        let mut customer_key = self.customer_key.next_value();
+        let mut delta = 1;
+        while customer_key % CUSTOMER_MORTALITY == 0 {
+            customer_key += delta;
+            customer_key = customer_key.min(self.max_customer_key);
+            delta *= -1;
+        }
+
+        let mut total_price = 0;
+        let mut shipped = 0;
+        let line_count = self.line_count.next_value();
+        for _ in 0..line_count {
+            let quantity = self.line_quantity.next_value() as i64;
+            let discount = self.line_discount.next_value() as i64;
+            let tax = self.line_tax.next_value() as i64;
+            let part_key = self.line_part_key.next_value();
+            let extended_price = part_price(part_key) * quantity;
+            let discounted_price = extended_price * (100 - discount);
+            total_price += ((discounted_price / 100) * (100 + tax)) / 100;
+            let ship_date = self.line_ship_date.next_value() + order_date;
+            if is_in_past(ship_date) {
+                shipped += 1;
+            }
+        }
+        let order_status = if shipped == line_count {
+            'F'
+        } else if shipped > 0 {
+            'P'
+        } else {
+            'O'
+        };
+
+        let order = Order {
+            order_key: order_key(self.index + 1),
+            customer_key,
+            order_status,
+            total_price,
+            order_date: to_epoch_date(order_date),
+            order_priority: self.order_priority.next_value(),
+            clerk: self.clerk.next_value(),
+            ship_priority: 0,
+            comment: self.comment.next_value(),
+        };
+        self.order_date.row_finished();
+        self.line_count.row_finished();
+        self.customer_key.row_finished();
+        self.order_priority.row_finished();
+        self.clerk.row_finished();
+        self.comment.row_finished();
+        self.line_quantity.row_finished();
+        self.line_discount.row_finished();
+        self.line_tax.row_finished();
+        self.line_part_key.row_finished();
+        self.line_ship_date.row_finished();
+        self.index += 1;
+        Some(order)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct LineItem {
+    pub order_key: i64,
+    pub part_key: i64,
+    pub supplier_key: i64,
+    pub line_number: i32,
+    pub quantity: i64,
+    pub extended_price: i64,
+    pub discount: i64,
+    pub tax: i64,
+    pub return_flag: &'static str,
+    pub line_status: char,
+    /// Days since 1970-01-01
+    pub ship_date: i32,
+    pub commit_date: i32,
+    pub receipt_date: i32,
+    pub ship_instructions: &'static str,
+    pub ship_mode: &'static str,
+    pub comment: &'static str,
+}
+
+impl fmt::Display for LineItem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}|{}|{}|{}|{}|{}|{}|{}|{}|{}|{}|{}|{}|{}|{}|{}|",
+            self.order_key,
+            self.part_key,
+            self.supplier_key,
+            self.line_number,
+            self.quantity,
+            format_money(self.extended_price),
+            format_money(self.discount),
+            format_money(self.tax),
+            self.return_flag,
+            self.line_status,
+            format_date(self.ship_date),
+            format_date(self.commit_date),
+            format_date(self.receipt_date),
+            self.ship_instructions,
+            self.ship_mode,
+            self.comment
+        )
+    }
+}
+
+/// Generates the line items of each order, so chunks are in terms of orders
+pub struct LineItemGenerator {
+    scale: f64,
+    index: i64,
+    end: i64,
+    line_number: i32,
+    line_count: i32,
+    order_date: i32,
+    order_date_random: RandomBoundedInt,
+    line_count_random: RandomBoundedInt,
+    quantity: RandomBoundedInt,
+    discount: RandomBoundedInt,
+    tax: RandomBoundedInt,
+    part_key: RandomBoundedLong,
+    supplier_number: RandomBoundedInt,
+    ship_date: RandomBoundedInt,
+    commit_date: RandomBoundedInt,
+    receipt_date: RandomBoundedInt,
+    return_flag: RandomString,
+    ship_instructions: RandomString,
+    ship_mode: RandomString,
+    comment: RandomText,
+}
+
+impl LineItemGenerator {
+    pub fn new(scale: f64, part: usize, part_count: usize, seed: Option<u64>) -> Self {
+        let (start, count) = chunk(ORDER_SCALE_BASE, scale, part, part_count);
+        let mut generator = Self {
+            scale,
+            index: start,
+            end: start + count,
+            line_number: 0,
+            line_count: 0,
+            order_date: 0,
+            order_date_random: order_date_random(seed),
+            line_count_random: line_count_random(seed),
+            quantity: quantity_random(seed),
+            discount: discount_random(seed),
+            tax: tax_random(seed),
+            part_key: part_key_random(scale, seed),
+            supplier_number: RandomBoundedInt::new(
+                stream_seed(2095021727, seed),
+                0,
+                3,
+                LINE_COUNT_MAX,
+            ),
+            ship_date: ship_date_random(seed),
+            commit_date: RandomBoundedInt::new(
+                stream_seed(904914315, seed),
+                COMMIT_DATE_MIN,
+                COMMIT_DATE_MAX,
+                LINE_COUNT_MAX,
+            ),
+            receipt_date: RandomBoundedInt::new(
+                stream_seed(373135028, seed),
+                RECEIPT_DATE_MIN,
+                RECEIPT_DATE_MAX,
+                LINE_COUNT_MAX,
+            ),
+            return_flag: RandomString::new(
+                stream_seed(717419739, seed),
+                Distribution::new(RETURN_FLAGS),
+                LINE_COUNT_MAX,
+            ),
+            ship_instructions: RandomString::new(
+                stream_seed(1371272478, seed),
+                Distribution::new(SHIP_INSTRUCTIONS),
+                LINE_COUNT_MAX,
+            ),
+            ship_mode: RandomString::new(
+                stream_seed(675466456, seed),
+                Distribution::new(SHIP_MODES),
+                LINE_COUNT_MAX,
+            ),
+            comment: RandomText::new(
+                stream_seed(1095462486, seed),
+                TextPool::get(),
+                27.0,
+                LINE_COUNT_MAX,
+            ),
+        };
+        generator.order_date_random.advance_rows(start);
+        generator.line_count_random.advance_rows(start);
+        generator.quantity.advance_rows(start);
+        generator.discount.advance_rows(start);
+        generator.tax.advance_rows(start);
+        generator.part_key.advance_rows(start);
+        generator.supplier_number.advance_rows(start);
+        generator.ship_date.advance_rows(start);
+        generator.commit_date.advance_rows(start);
+        generator.receipt_date.advance_rows(start);
+        generator.return_flag.advance_rows(start);
+        generator.ship_instructions.advance_rows(start);
+        generator.ship_mode.advance_rows(start);
+        generator.comment.advance_rows(start);
+        if count > 0 {
+            generator.start_order();
+        }
+        generator
+    }
+
+    fn start_order(&mut self) {
+        self.order_date = self.order_date_random.next_value();
+        self.line_count = self.line_count_random.next_value();
+        self.line_number = 0;
+    }
+
+    fn finish_order(&mut self) {
+        self.order_date_random.row_finished();
+        self.line_count_random.row_finished();
+        self.quantity.row_finished();
+        self.discount.row_finished();
+        self.tax.row_finished();
+        self.part_key.row_finished();
+        self.supplier_number.row_finished();
+        self.ship_date.row_finished();
+        self.commit_date.row_finished();
+        self.receipt_date.row_finished();
+        self.return_flag.row_finished();
+        self.ship_instructions.row_finished();
+        self.ship_mode.row_finished();
+        self.comment.row_finished();
+    }
+
+    fn line_item(&mut self) -> LineItem {
+        let quantity = self.quantity.next_value() as i64;
+        let discount = self.discount.next_value() as i64;
+        let tax = self.tax.next_value() as i64;
+        let part_key = self.part_key.next_value();
+        let supplier_number = self.supplier_number.next_value() as i64;
+        let ship_date = self.ship_date.next_value() + self.order_date;
+        let commit_date = self.commit_date.next_value() + self.order_date;
+        let receipt_date = self.receipt_date.next_value() + ship_date;
+        let return_flag = if is_in_past(receipt_date) {
+            self.return_flag.next_value()
+        } else {
+            "N"
+        };
+        let line_status = if is_in_past(ship_date) { 'F' } else { 'O' };
+        LineItem {
+            order_key: order_key(self.index + 1),
+            part_key,
+            supplier_key: part_supplier(part_key, supplier_number, self.scale),
+            line_number: self.line_number + 1,
+            quantity,
+            extended_price: part_price(part_key) * quantity,
+            discount,
+            tax,
+            return_flag,
+            line_status,
+            ship_date: to_epoch_date(ship_date),
+            commit_date: to_epoch_date(commit_date),
+            receipt_date: to_epoch_date(receipt_date),
+            ship_instructions: self.ship_instructions.next_value(),
+            ship_mode: self.ship_mode.next_value(),
+            comment: self.comment.next_value(),
+        }
+    }
+}
+
+impl Iterator for LineItemGenerator {
+    type Item = LineItem;
+
+    fn next(&mut self) -> Option<LineItem> {
+        if self.index >= self.end {
+            return None;
+        }
+        let line_item = self.line_item();
+        self.line_number += 1;
+        if self.line_number >= self.line_count {
+            self.finish_order();
+            self.index += 1;
+            if self.index < self.end {
+                self.start_order();
+            }
+        }
+        Some(line_item)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Nation {
+    pub nation_key: i64,
+    pub name: &'static str,
+    pub region_key: i64,
+    pub comment: &'static str,
+}
+
+impl fmt::Display for Nation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}|{}|{}|{}|",
+            self.nation_key, self.name, self.region_key, self.comment
+        )
+    }
+}
+
+/// The nation table does not depend on the scale factor and is never split into chunks
+pub struct NationGenerator {
+    index: usize,
+    comment: RandomText,
+}
+
+impl NationGenerator {
+    pub fn new() -> Self {
+        Self {
+            index: 0,
+            comment: RandomText::new(606179079, TextPool::get(), 72.0, 1),
+        }
+    }
+}
+
+impl Default for NationGenerator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Iterator for NationGenerator {
+    type Item = Nation;
+
+    fn next(&mut self) -> Option<Nation> {
+        let (name, region_key) = NATIONS.get(self.index)?;
+        let nation = Nation {
+            nation_key: self.index as i64,
+            name,
+            region_key: *region_key,
+            comment: self.comment.next_value(),
+        };
+        self.comment.row_finished();
+        self.index += 1;
+        Some(nation)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Region {
+    pub region_key: i64,
+    pub name: &'static str,
+    pub comment: &'static str,
+}
+
+impl fmt::Display for Region {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}|{}|{}|", self.region_key, self.name, self.comment)
+    }
+}
+
+/// The region table does not depend on the scale factor and is never split into chunks
+pub struct RegionGenerator {
+    index: usize,
+    comment: RandomText,
+}
+
+impl RegionGenerator {
+    pub fn new() -> Self {
+        Self {
+            index: 0,
+            comment: RandomText::new(1500869201, TextPool::get(), 72.0, 1),
+        }
+    }
+}
+
+impl Default for RegionGenerator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Iterator for RegionGenerator {
+    type Item = Region;
+
+    fn next(&mut self) -> Option<Region> {
+        let name = REGIONS.get(self.index)?;
+        let region = Region {
+            region_key: self.index as i64,
+            name,
+            comment: self.comment.next_value(),
+        };
+        self.comment.row_finished();
+        self.index += 1;
+        Some(region)
+    }
+}
@@ -0,0 +1,886 @@
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::fmt::Display;
+use std::fs::{self, File};
+use std::io::{BufWriter, Error, ErrorKind, Result, Write};
+use std::path::Path;
+use std::sync::Arc;
+use std::thread;
+use std::time::Instant;
+
+use async_trait::async_trait;
+use datafusion::arrow::datatypes::{DataType, Field, Schema};
+use datafusion::error::DataFusionError;
+use datafusion::parquet::arrow::ArrowWriter;
+use datafusion::parquet::basic::Compression;
+use datafusion::parquet::file::properties::WriterProperties;
+use tracing::{debug, info, info_span};
+
+use crate::progress::{Progress, ProgressEvent};
+use crate::{
+    check_scale, dbgen_command, generator_work_dir, join_generators, move_or_copy, run_logged,
+    unknown_table, ForeignKey, TableType, Tpc,
+};
+
+pub mod batch;
+mod distribution;
+pub mod generator;
+mod random;
+mod text;
+
+use generator::*;
+use text::TextPool;
+
+/// Tables that are split into chunks when generating partitions
+const PARTITIONED_TABLES: [&str; 6] = [
+    "customer", "lineitem", "orders", "part", "partsupp", "supplier",
+];
+
+/// Approximate size of the `.tbl` files at scale factor 1, which is used to estimate the time
+/// remaining
+const RAW_BYTES_PER_SCALE_FACTOR: f64 = 1.1e9;
+
+/// Number of line items generated by dbgen at each scale factor
+const LINEITEM_ROW_COUNTS: [(f64, u64); 12] = [
+    (0.01, 60_175),
+    (0.1, 600_572),
+    (1.0, 6_001_215),
+    (10.0, 59_986_052),
+    (30.0, 179_998_372),
+    (100.0, 600_037_902),
+    (300.0, 1_799_989_091),
+    (1000.0, 5_999_989_709),
+    (3000.0, 18_000_048_306),
+    (10000.0, 59_999_994_267),
+    (30000.0, 179_999_978_268),
+    (100000.0, 599_999_969_200),
+];
+
+pub struct TpcH {
+    /// Generate the skewed data of JCC-H instead of the uniform data of TPC-H
+    skewed: bool,
+}
+
+impl TpcH {
+    pub fn new() -> Self {
+        Self { skewed: false }
+    }
+
+    /// TPC-H with the skewed data of JCC-H, which is generated by the JCC-H fork of dbgen with
+    /// the `-k` option. The tables and schemas are the same as for TPC-H.
+    pub fn skewed() -> Self {
+        Self { skewed: true }
+    }
+
+    /// Generate refresh data for the RF1 and RF2 refresh functions using `dbgen -U`. Each
+    /// update set consists of `orders.tbl.u<n>`, `lineitem.tbl.u<n>` and `delete.<n>`, which
+    /// are moved to the output path.
+    pub fn generate_refresh_data(
+        &self,
+        scale: f64,
+        update_sets: usize,
+        generator_path: &str,
+        output_path: &str,
+    ) -> Result<()> {
+        check_scale(scale)?;
+        let start = Instant::now();
+
+        if !Path::new(&output_path).exists() {
+            debug!(path = output_path, "Creating directory");
+            fs::create_dir(output_path)?;
+        }
+
+        let work_dir = generator_work_dir(output_path)?;
+
+        info!(update_sets, "Generating update sets");
+        run_logged(
+            dbgen_command(generator_path, work_dir.path())?
+                .arg("-f")
+                .arg("-s")
+                .arg(format!("{}", scale))
+                .arg("-U")
+                .arg(format!("{}", update_sets)),
+            output_path,
+            "dbgen-refresh",
+        )?;
+
+        info!(
+            update_sets,
+            scale,
+            elapsed = ?start.elapsed(),
+            "Generated TPC-H update sets"
+        );
+
+        for i in 1..=update_sets {
+            for filename in [
+                format!("orders.tbl.u{}", i),
+                format!("lineitem.tbl.u{}", i),
+                format!("delete.{}", i),
+            ] {
+                let source = format!("{}/{}", work_dir.path().display(), filename);
+                let dest = format!("{}/{}", output_path, filename);
+                if Path::new(&source).exists() {
+                    move_or_copy(Path::new(&source), Path::new(&dest))?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl TpcH {
+    /// Generate the data without `dbgen`, producing the same rows. Each partition is written
+    /// to `<table>.tbl/part-<n>.tbl` by its own thread, and the nation and region tables are
+    /// written to `part-0.tbl`.
+    ///
+    /// With a `seed`, every random stream starts from a seed derived from it instead of the
+    /// dbgen seed, which produces a different data set with the same distributions. The nation
+    /// and region tables are the same for every seed.
+    pub fn generate_native(
+        &self,
+        scale: f64,
+        partitions: usize,
+        output_path: &str,
+        seed: Option<u64>,
+        progress: &Progress,
+    ) -> Result<()> {
+        check_scale(scale)?;
+        let start = Instant::now();
+
+        if !Path::new(&output_path).exists() {
+            debug!(path = output_path, "Creating directory");
+            fs::create_dir(output_path)?;
+        }
+        for table in self.get_table_names() {
+            let output_dir = format!("{}/{}.tbl", output_path, table);
+            if !Path::new(&output_dir).exists() {
+                debug!(path = %output_dir, "Creating directory");
+                fs::create_dir(&output_dir)?;
+            }
+        }
+
+        // the text pool is shared by all partitions so generate it before starting the threads
+        debug!("Generating text pool");
+        TextPool::get();
+
+        progress.report(ProgressEvent::GenerationStarted {
+            children: partitions,
+            expected_bytes: Some((RAW_BYTES_PER_SCALE_FACTOR * scale) as u64),
+        });
+        let watcher = progress.watch(output_path, &self.get_table_names());
+
+        let mut handles = vec![];
+        for i in 1..=partitions {
+            let output_path = output_path.to_owned();
+            let progress = progress.clone();
+            let span = info_span!("partition", part = i);
+            handles.push(thread::spawn(move || -> Result<()> {
+                let _span = span.entered();
+                info!("Generating partition {} of {}", i, partitions);
+                for table in PARTITIONED_TABLES {
+                    let filename = format!("{}/{}.tbl/part-{}.tbl", output_path, table, i);
+                    let mut writer = BufWriter::new(File::create(filename)?);
+                    write_chunk(table, scale, i, partitions, seed, &mut writer)?;
+                    writer.flush()?;
+                }
+                progress.report(ProgressEvent::ChildFinished { child: i });
+                Ok(())
+            }));
+        }
+
+        for table in ["nation", "region"] {
+            let filename = format!("{}/{}.tbl/part-0.tbl", output_path, table);
+            let mut writer = BufWriter::new(File::create(filename)?);
+            write_chunk(table, scale, 1, 1, seed, &mut writer)?;
+            writer.flush()?;
+        }
+
+        // wait for all threads to finish
+        for h in handles {
+            h.join().map_err(|_| {
+                DataFusionError::Execution("Generator thread panicked".to_owned())
+            })??;
+        }
+
+        drop(watcher);
+        progress.report(ProgressEvent::Finished);
+
+        info!(
+            scale,
+            partitions,
+            elapsed = ?start.elapsed(),
+            "Generated TPC-H data"
+        );
+
+        Ok(())
+    }
+}
+
+impl TpcH {
+    /// Generate the data straight to Parquet without writing any `.tbl` files, in the same
+    /// layout as `convert_to_parquet`. Partition `n` of each table is written to
+    /// `<table>.parquet/part-<n - 1>.parquet` by its own thread. See `generate_native` for the
+    /// `seed`.
+    pub fn generate_parquet(
+        &self,
+        scale: f64,
+        partitions: usize,
+        output_path: &str,
+        seed: Option<u64>,
+        progress: &Progress,
+    ) -> datafusion::error::Result<()> {
+        check_scale(scale)?;
+        let start = Instant::now();
+
+        if !Path::new(&output_path).exists() {
+            debug!(path = output_path, "Creating directory");
+            fs::create_dir(output_path)?;
+        }
+        for table in self.get_table_names() {
+            let output_dir = format!("{}/{}.parquet", output_path, table);
+            if !Path::new(&output_dir).exists() {
+                debug!(path = %output_dir, "Creating directory");
+                fs::create_dir(&output_dir)?;
+            }
+        }
+
+        debug!("Generating text pool");
+        TextPool::get();
+
+        // the size of the compressed output is not known in advance
+        progress.report(ProgressEvent::GenerationStarted {
+            children: partitions,
+            expected_bytes: None,
+        });
+        let watcher = progress.watch(output_path, &self.get_table_names());
+
+        let mut handles = vec![];
+        for i in 1..=partitions {
+            let output_path = output_path.to_owned();
+            let progress = progress.clone();
+            let span = info_span!("partition", part = i);
+            handles.push(thread::spawn(move || -> datafusion::error::Result<()> {
+                let _span = span.entered();
+                info!("Generating partition {} of {}", i, partitions);
+                for table in PARTITIONED_TABLES {
+                    let filename =
+                        format!("{}/{}.parquet/part-{}.parquet", output_path, table, i - 1);
+                    write_parquet(table, scale, i, partitions, seed, &filename)?;
+                }
+                progress.report(ProgressEvent::ChildFinished { child: i });
+                Ok(())
+            }));
+        }
+
+        for table in ["nation", "region"] {
+            let filename = format!("{}/{}.parquet/part-0.parquet", output_path, table);
+            write_parquet(table, scale, 1, 1, seed, &filename)?;
+        }
+
+        // wait for all threads to finish
+        for h in handles {
+            h.join().map_err(|_| {
+                DataFusionError::Execution("Generator thread panicked".to_owned())
+            })??;
+        }
+
+        drop(watcher);
+        progress.report(ProgressEvent::Finished);
+
+        info!(
+            scale,
+            partitions,
+            elapsed = ?start.elapsed(),
+            "Generated TPC-H data"
+        );
+
+        Ok(())
+    }
+}
+
+fn write_parquet(
+    table: &str,
+    scale: f64,
+    part: usize,
+    part_count: usize,
+    seed: Option<u64>,
+    filename: &str,
+) -> datafusion::error::Result<()> {
+    let props = WriterProperties::builder()
+        .set_compression(Compression::SNAPPY)
+        .build();
+    let batches = batch::record_batches(table, scale, part, part_count, seed, 8192)?;
+    let schema = Arc::new(TpcH::new().get_schema(table)?);
+    let mut writer = ArrowWriter::try_new(File::create(filename)?, schema, Some(props))?;
+    for batch in batches {
+        writer.write(&batch?)?;
+    }
+    writer.close()?;
+    Ok(())
+}
+
+/// Write one chunk of a table in the `.tbl` format, which is equivalent to the output of
+/// `dbgen -T <table> -C <part_count> -S <part>`. Parts are numbered from 1. See
+/// `TpcH::generate_native` for the `seed`.
+pub fn write_chunk(
+    table: &str,
+    scale: f64,
+    part: usize,
+    part_count: usize,
+    seed: Option<u64>,
+    writer: &mut dyn Write,
+) -> Result<()> {
+    match table {
+        "part" => write_rows(PartGenerator::new(scale, part, part_count, seed), writer),
+        "supplier" => write_rows(
+            SupplierGenerator::new(scale, part, part_count, seed),
+            writer,
+        ),
+        "partsupp" => write_rows(
+            PartSuppGenerator::new(scale, part, part_count, seed),
+            writer,
+        ),
+        "customer" => write_rows(
+            CustomerGenerator::new(scale, part, part_count, seed),
+            writer,
+        ),
+        "orders" => write_rows(OrderGenerator::new(scale, part, part_count, seed), writer),
+        "lineitem" => write_rows(
+            LineItemGenerator::new(scale, part, part_count, seed),
+            writer,
+        ),
+        "nation" => write_rows(NationGenerator::new(), writer),
+        "region" => write_rows(RegionGenerator::new(), writer),
+        _ => Err(Error::new(
+            ErrorKind::InvalidInput,
+            format!("Unknown TPC-H table '{}'", table),
+        )),
+    }
+}
+
+fn write_rows<T: Display>(rows: impl Iterator<Item = T>, writer: &mut dyn Write) -> Result<()> {
+    for row in rows {
+        writeln!(writer, "{}", row)?;
+    }
+    Ok(())
+}
+
+impl Default for TpcH {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl Tpc for TpcH {
+    fn generate(
+        &self,
+        scale: f64,
+        partitions: usize,
+        generator_path: &str,
+        output_path: &str,
+        seed: Option<u64>,
+        progress: &Progress,
+    ) -> Result<()> {
+        check_scale(scale)?;
+        if seed.is_some() {
+            return Err(Error::new(
+                ErrorKind::Unsupported,
+                "dbgen does not support setting a seed",
+            ));
+        }
+
+        if !Path::new(&output_path).exists() {
+            debug!(path = output_path, "Creating directory");
+            fs::create_dir(output_path)?;
+        }
+
+        // each partition is generated in its own working directory
+        let work_dirs = (0..partitions)
+            .map(|_| generator_work_dir(output_path))
+            .collect::<Result<Vec<_>>>()?;
+
+        let mut handles = vec![];
+
+        let start = Instant::now();
+
+        let skew_args: &[&str] = if self.skewed { &["-k"] } else { &[] };
+
+        progress.report(ProgressEvent::GenerationStarted {
+            children: partitions,
+            expected_bytes: Some((RAW_BYTES_PER_SCALE_FACTOR * scale) as u64),
+        });
+        let watcher = progress.watch(output_path, &self.get_table_names());
+
+        if partitions == 1 {
+            let mut command = dbgen_command(generator_path, work_dirs[0].path())?;
+            let output_path = output_path.to_owned();
+            let progress = progress.clone();
+            let span = info_span!("partition", part = 1);
+            handles.push(thread::spawn(move || {
+                let _span = span.entered();
+                info!("Generating partition 1 of 1");
+                command
+                    .arg("-f")
+                    .args(skew_args)
+                    .arg("-s")
+                    .arg(format!("{}", scale));
+                let result = run_logged(&mut command, &output_path, "dbgen-1");
+                progress.report(ProgressEvent::ChildFinished { child: 1 });
+                result
+            }));
+        } else {
+            for (i, work_dir) in (1..=partitions).zip(&work_dirs) {
+                let mut command = dbgen_command(generator_path, work_dir.path())?;
+                let output_path = output_path.to_owned();
+                let progress = progress.clone();
+                let span = info_span!("partition", part = i);
+                handles.push(thread::spawn(move || {
+                    let _span = span.entered();
+                    info!("Generating partition {} of {}", i, partitions);
+                    command
+                        .arg("-f")
+                        .args(skew_args)
+                        .arg("-s")
+                        .arg(format!("{}", scale))
+                        .arg("-C")
+                        .arg(format!("{}", partitions))
+                        .arg("-S")
+                        .arg(format!("{}", i));
+                    let result = run_logged(&mut command, &output_path, &format!("dbgen-{}", i));
+                    progress.report(ProgressEvent::ChildFinished { child: i });
+                    result
+                }));
+            }
+        }
+
+        // wait for all threads to finish
+        join_generators(handles)?;
+
+        let duration = start.elapsed();
+
+        info!(
+            scale,
+            partitions,
+            elapsed = ?duration,
+            "Generated {} data",
+            if self.skewed { "JCC-H" } else { "TPC-H" }
+        );
+
+        let tables = [
+            "customer", "lineitem", "nation", "orders", "part", "partsupp", "region", "supplier",
+        ];
+
+        for table in &tables {
+            let output_dir = format!("{}/{}.tbl", output_path, table);
+            if !Path::new(&output_dir).exists() {
+                debug!(path = %output_dir, "Creating directory");
+                fs::create_dir(&output_dir)?;
+            }
+
+            for work_dir in &work_dirs {
+                let filename = format!("{}/{}.tbl", work_dir.path().display(), table);
+                let filename2 = format!("{}/part-0.dat", output_dir);
+                if Path::new(&filename).exists() {
+                    move_or_copy(Path::new(&filename), Path::new(&filename2))?;
+                }
+            }
+
+            if partitions == 1 {
+                let filename = format!("{}/{}.tbl", work_dirs[0].path().display(), table);
+                let filename2 = format!("{}/part-0.tbl", output_dir);
+                if Path::new(&filename).exists() {
+                    move_or_copy(Path::new(&filename), Path::new(&filename2))?;
+                }
+            } else {
+                for (i, work_dir) in (1..=partitions).zip(&work_dirs) {
+                    let filename = format!("{}/{}.tbl.{}", work_dir.path().display(), table, i);
+                    let filename2 = format!("{}/part-{}.tbl", output_dir, i);
+                    if Path::new(&filename).exists() {
+                        move_or_copy(Path::new(&filename), Path::new(&filename2))?;
+                    }
+                }
+            }
+        }
+
+        drop(watcher);
+        progress.report(ProgressEvent::Finished);
+
+        Ok(())
+    }
+
+    fn get_table_names(&self) -> Vec<&str> {
+        vec![
+            "customer", "lineitem", "nation", "orders", "part", "partsupp", "region", "supplier",
+        ]
+    }
+
+    fn get_schema(&self, table: &str) -> Result<Schema> {
+        // note that the schema intentionally uses signed integers so that any generated Parquet
+        // files can also be used to benchmark tools that only support signed integers, such as
+        // Apache Spark
+
+        Ok(match table {
+            "part" => Schema::new(vec![
+                Field::new("p_partkey", DataType::Int64, false),
+                Field::new("p_name", DataType::Utf8, false),
+                Field::new("p_mfgr", DataType::Utf8, false),
+                Field::new("p_brand", DataType::Utf8, false),
+                Field::new("p_type", DataType::Utf8, false),
+                Field::new("p_size", DataType::Int32, false),
+                Field::new("p_container", DataType::Utf8, false),
+                Field::new("p_retailprice", DataType::Decimal128(11, 2), false),
+                Field::new("p_comment", DataType::Utf8, false),
+                Field::new("ignore", DataType::Utf8, true),
+            ]),
+
+            "supplier" => Schema::new(vec![
+                Field::new("s_suppkey", DataType::Int64, false),
+                Field::new("s_name", DataType::Utf8, false),
+                Field::new("s_address", DataType::Utf8, false),
+                Field::new("s_nationkey", DataType::Int64, false),
+                Field::new("s_phone", DataType::Utf8, false),
+                Field::new("s_acctbal", DataType::Decimal128(11, 2), false),
+                Field::new("s_comment", DataType::Utf8, false),
+                Field::new("ignore", DataType::Utf8, true),
+            ]),
+
+            "partsupp" => Schema::new(vec![
+                Field::new("ps_partkey", DataType::Int64, false),
+                Field::new("ps_suppkey", DataType::Int64, false),
+                Field::new("ps_availqty", DataType::Int32, false),
+                Field::new("ps_supplycost", DataType::Decimal128(11, 2), false),
+                Field::new("ps_comment", DataType::Utf8, false),
+                Field::new("ignore", DataType::Utf8, true),
+            ]),
+
+            "customer" => Schema::new(vec![
+                Field::new("c_custkey", DataType::Int64, false),
+                Field::new("c_name", DataType::Utf8, false),
+                Field::new("c_address", DataType::Utf8, false),
+                Field::new("c_nationkey", DataType::Int64, false),
+                Field::new("c_phone", DataType::Utf8, false),
+                Field::new("c_acctbal", DataType::Decimal128(11, 2), false),
+                Field::new("c_mktsegment", DataType::Utf8, false),
+                Field::new("c_comment", DataType::Utf8, false),
+                Field::new("ignore", DataType::Utf8, true),
+            ]),
+
+            "orders" => Schema::new(vec![
+                Field::new("o_orderkey", DataType::Int64, false),
+                Field::new("o_custkey", DataType::Int64, false),
+                Field::new("o_orderstatus", DataType::Utf8, false),
+                Field::new("o_totalprice", DataType::Decimal128(11, 2), false),
+                Field::new("o_orderdate", DataType::Date32, false),
+                Field::new("o_orderpriority", DataType::Utf8, false),
+                Field::new("o_clerk", DataType::Utf8, false),
+                Field::new("o_shippriority", DataType::Int32, false),
+                Field::new("o_comment", DataType::Utf8, false),
+                Field::new("ignore", DataType::Utf8, true),
+            ]),
+
+            "lineitem" => Schema::new(vec![
+                Field::new("l_orderkey", DataType::Int64, false),
+                Field::new("l_partkey", DataType::Int64, false),
+                Field::new("l_suppkey", DataType::Int64, false),
+                Field::new("l_linenumber", DataType::Int32, false),
+                Field::new("l_quantity", DataType::Decimal128(11, 2), false),
+                Field::new("l_extendedprice", DataType::Decimal128(11, 2), false),
+                Field::new("l_discount", DataType::Decimal128(11, 2), false),
+                Field::new("l_tax", DataType::Decimal128(11, 2), false),
+                Field::new("l_returnflag", DataType::Utf8, false),
+                Field::new("l_linestatus", DataType::Utf8, false),
+                Field::new("l_shipdate", DataType::Date32, false),
+                Field::new("l_commitdate", DataType::Date32, false),
+                Field::new("l_receiptdate", DataType::Date32, false),
+                Field::new("l_shipinstruct", DataType::Utf8, false),
+                Field::new("l_shipmode", DataType::Utf8, false),
+                Field::new("l_comment", DataType::Utf8, false),
+                Field::new("ignore", DataType::Utf8, true),
+            ]),
+
+            "nation" => Schema::new(vec![
+                Field::new("n_nationkey", DataType::Int64, false),
+                Field::new("n_name", DataType::Utf8, false),
+                Field::new("n_regionkey", DataType::Int64, false),
+                Field::new("n_comment", DataType::Utf8, false),
+                Field::new("ignore", DataType::Utf8, true),
+            ]),
+
+            "region" => Schema::new(vec![
+                Field::new("r_regionkey", DataType::Int64, false),
+                Field::new("r_name", DataType::Utf8, false),
+                Field::new("r_comment", DataType::Utf8, false),
+                Field::new("ignore", DataType::Utf8, true),
+            ]),
+
+            _ => return Err(unknown_table("TPC-H", table)),
+        })
+    }
+
+    fn get_table_ext(&self) -> &str {
+        "tbl"
+    }
+
+    fn get_primary_key(&self, table: &str) -> Result<Vec<&str>> {
+        Ok(match table {
+            "part" => vec!["p_partkey"],
+            "supplier" => vec!["s_suppkey"],
+            "partsupp" => vec!["ps_partkey", "ps_suppkey"],
+            "customer" => vec!["c_custkey"],
+            "orders" => vec!["o_orderkey"],
+            "lineitem" => vec!["l_orderkey", "l_linenumber"],
+            "nation" => vec!["n_nationkey"],
+            "region" => vec!["r_regionkey"],
+            _ => return Err(unknown_table("TPC-H", table)),
+        })
+    }
+
+    fn get_foreign_keys(&self, table: &str) -> Result<Vec<ForeignKey>> {
+        Ok(match table {
+            "supplier" => vec![ForeignKey::new(
+                &["s_nationkey"],
+                "nation",
+                &["n_nationkey"],
+            )],
+            "partsupp" => vec![
+                ForeignKey::new(&["ps_partkey"], "part", &["p_partkey"]),
+                ForeignKey::new(&["ps_suppkey"], "supplier", &["s_suppkey"]),
+            ],
+            "customer" => vec![ForeignKey::new(
+                &["c_nationkey"],
+                "nation",
+                &["n_nationkey"],
+            )],
+            "orders" => vec![ForeignKey::new(&["o_custkey"], "customer", &["c_custkey"])],
+            "lineitem" => vec![
+                ForeignKey::new(&["l_orderkey"], "orders", &["o_orderkey"]),
+                ForeignKey::new(&["l_partkey"], "part", &["p_partkey"]),
+                ForeignKey::new(&["l_suppkey"], "supplier", &["s_suppkey"]),
+                ForeignKey::new(
+                    &["l_partkey", "l_suppkey"],
+                    "partsupp",
+                    &["ps_partkey", "ps_suppkey"],
+                ),
+            ],
+            "nation" => vec![ForeignKey::new(
+                &["n_regionkey"],
+                "region",
+                &["r_regionkey"],
+            )],
+            "part" | "region" => vec![],
+            _ => return Err(unknown_table("TPC-H", table)),
+        })
+    }
+
+    fn get_row_count(&self, table: &str, scale: f64) -> Option<u64> {
+        // dbgen truncates the number of rows at fractional scale factors
+        let rows = |base: f64| Some((base * scale) as u64);
+        match table {
+            // the skew changes the distribution of orders and line items, so the row counts of
+            // the specification do not apply to them
+            "orders" | "lineitem" if self.skewed => None,
+            "part" => rows(200_000.0),
+            "supplier" => rows(10_000.0),
+            // four suppliers per part
+            "partsupp" => rows(200_000.0).map(|parts| parts * 4),
+            "customer" => rows(150_000.0),
+            "orders" => rows(1_500_000.0),
+            // the number of line items per order is random so the total is only known for the
+            // scale factors listed in the specification, and the small scale factors commonly
+            // used for testing
+            "lineitem" => LINEITEM_ROW_COUNTS
+                .iter()
+                .find(|(sf, _)| (sf - scale).abs() < 1e-9)
+                .map(|(_, rows)| *rows),
+            "nation" => Some(25),
+            "region" => Some(5),
+            _ => None,
+        }
+    }
+
+    fn get_table_type(&self, table: &str) -> Result<TableType> {
+        match table {
+            "lineitem" | "orders" | "partsupp" => Ok(TableType::Fact),
+            "customer" | "nation" | "part" | "region" | "supplier" => Ok(TableType::Dimension),
+            _ => Err(unknown_table("TPC-H", table)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Compare the first rows of a table at scale factor 1 with the output of dbgen
+    fn assert_matches_dbgen(table: &str, expected: &str) {
+        let mut output = vec![];
+        // the first of 1000 chunks has at least as many rows as the fixtures
+        write_chunk(table, 1.0, 1, 1000, None, &mut output).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        let expected: Vec<&str> = expected.lines().collect();
+        let actual: Vec<&str> = output.lines().take(expected.len()).collect();
+        assert_eq!(actual, expected, "{} differs from dbgen", table);
+    }
+
+    #[test]
+    fn nation_matches_dbgen() {
+        assert_matches_dbgen("nation", include_str!("testdata/nation.tbl"));
+    }
+
+    #[test]
+    fn region_matches_dbgen() {
+        assert_matches_dbgen("region", include_str!("testdata/region.tbl"));
+    }
+
+    #[test]
+    fn part_matches_dbgen() {
+        assert_matches_dbgen("part", include_str!("testdata/part.tbl"));
+    }
+
+    #[test]
+    fn supplier_matches_dbgen() {
+        assert_matches_dbgen("supplier", include_str!("testdata/supplier.tbl"));
+    }
+
+    #[test]
+    fn partsupp_matches_dbgen() {
+        assert_matches_dbgen("partsupp", include_str!("testdata/partsupp.tbl"));
+    }
+
+    #[test]
+    fn customer_matches_dbgen() {
+        assert_matches_dbgen("customer", include_str!("testdata/customer.tbl"));
+    }
+
+    #[test]
+    fn orders_matches_dbgen() {
+        assert_matches_dbgen("orders", include_str!("testdata/orders.tbl"));
+    }
+
+    #[test]
+    fn lineitem_matches_dbgen() {
+        assert_matches_dbgen("lineitem", include_str!("testdata/lineitem.tbl"));
+    }
+
+    /// Compare whole files written by dbgen into `testdata/dbgen/<run>` by `testdata/dbgen.sh`
+    fn assert_files_match_dbgen(run: &str, scale: f64, part: usize, part_count: usize) {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("src/tpch/testdata/dbgen")
+            .join(run);
+        // dbgen only writes the nation and region tables in full and suffixes chunks
+        let tables: &[&str] = if part_count == 1 {
+            &[
+                "customer", "lineitem", "nation", "orders", "part", "partsupp", "region",
+                "supplier",
+            ]
+        } else {
+            &[
+                "customer", "lineitem", "orders", "part", "partsupp", "supplier",
+            ]
+        };
+        for table in tables {
+            let file_name = if part_count == 1 {
+                format!("{}.tbl", table)
+            } else {
+                format!("{}.tbl.{}", table, part)
+            };
+            let expected = fs::read_to_string(dir.join(&file_name))
+                .unwrap_or_else(|e| panic!("{}: {}", dir.join(&file_name).display(), e));
+            let mut output = vec![];
+            write_chunk(table, scale, part, part_count, None, &mut output).unwrap();
+            let output = String::from_utf8(output).unwrap();
+            let expected: Vec<&str> = expected.lines().collect();
+            let actual: Vec<&str> = output.lines().collect();
+            assert_eq!(actual, expected, "{} differs from dbgen", file_name);
+        }
+    }
+
+    #[test]
+    #[ignore = "requires the output of src/tpch/testdata/dbgen.sh"]
+    fn fractional_scale_matches_dbgen() {
+        assert_files_match_dbgen("sf0.01", 0.01, 1, 1);
+    }
+
+    #[test]
+    #[ignore = "requires the output of src/tpch/testdata/dbgen.sh"]
+    fn later_chunk_matches_dbgen() {
+        assert_files_match_dbgen("sf0.01-c4-s3", 0.01, 3, 4);
+    }
+
+    #[test]
+    fn chunks_concatenate_to_single_chunk() {
+        for table in [
+            "customer", "lineitem", "orders", "part", "partsupp", "supplier",
+        ] {
+            let mut expected = vec![];
+            write_chunk(table, 0.01, 1, 1, None, &mut expected).unwrap();
+            let mut actual = vec![];
+            for part in 1..=4 {
+                write_chunk(table, 0.01, part, 4, None, &mut actual).unwrap();
+            }
+            assert!(actual == expected, "chunks of {} differ", table);
+        }
+    }
+
+    #[test]
+    fn unknown_table_is_an_error() {
+        let tpch = TpcH::new();
+        for err in [
+            tpch.get_schema("lineorder").unwrap_err(),
+            tpch.get_primary_key("lineorder").unwrap_err(),
+            tpch.get_foreign_keys("lineorder").unwrap_err(),
+            tpch.get_table_type("lineorder").unwrap_err(),
+        ] {
+            assert_eq!(ErrorKind::InvalidInput, err.kind());
+            assert_eq!("Unknown TPC-H table 'lineorder'", err.to_string());
+        }
+        assert_eq!(TableType::Fact, tpch.get_table_type("lineitem").unwrap());
+        assert_eq!(TableType::Dimension, tpch.get_table_type("nation").unwrap());
+    }
+
+    #[test]
+    fn write_chunk_rejects_unknown_table() {
+        let err = write_chunk("nations", 1.0, 1, 1, None, &mut vec![]).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    fn fractional_scale_matches_row_counts() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let output_path = dir.path().to_str().unwrap();
+        let tpch = TpcH::new();
+        tpch.generate_native(0.01, 2, output_path, None, &Progress::none())?;
+        for table in tpch.get_table_names() {
+            let mut rows = 0;
+            for entry in fs::read_dir(dir.path().join(format!("{}.tbl", table)))? {
+                rows += fs::read_to_string(entry?.path())?.lines().count() as u64;
+            }
+            assert_eq!(tpch.get_row_count(table, 0.01), Some(rows), "{}", table);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn generate_rejects_invalid_scale() {
+        let dir = tempfile::tempdir().unwrap();
+        let output_path = dir.path().to_str().unwrap();
+        for scale in [0.0, -1.0, f64::NAN] {
+            let err = TpcH::new()
+                .generate_native(scale, 1, output_path, None, &Progress::none())
+                .unwrap_err();
+            assert_eq!(err.kind(), ErrorKind::InvalidInput);
+        }
+    }
+}
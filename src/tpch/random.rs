@@ -0,0 +1,421 @@
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Random number streams matching those used by `dbgen`. Every column has its own stream with a
+//! fixed seed and a fixed number of values consumed per row, so that any row can be generated
+//! without generating the rows before it.
+
+use super::distribution::Distribution;
+use super::text::TextPool;
+
+const MULTIPLIER: i64 = 16807;
+const MODULUS: i64 = 2147483647;
+
+/// Seed of a stream for a data set generated with a user-defined seed. Without one, the streams
+/// use the dbgen seeds. Every stream with the same dbgen seed gets the same new seed, so that the
+/// order and line item generators still agree with each other.
+pub fn stream_seed(dbgen_seed: i64, seed: Option<u64>) -> i64 {
+    let seed = match seed {
+        Some(seed) => seed,
+        None => return dbgen_seed,
+    };
+    // SplitMix64 finalizer, mapped to a valid seed in 1..MODULUS
+    let mut hash = (dbgen_seed as u64) ^ seed.wrapping_mul(0x9e3779b97f4a7c15);
+    hash = (hash ^ (hash >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    hash = (hash ^ (hash >> 27)).wrapping_mul(0x94d049bb133111eb);
+    hash ^= hash >> 31;
+    (hash % (MODULUS as u64 - 1)) as i64 + 1
+}
+
+/// Park-Miller "minimal standard" generator, as implemented by `NextRand` and `UnifInt` in dbgen
+#[derive(Debug, Clone)]
+pub struct RandomInt {
+    seed: i64,
+    usage_per_row: i32,
+    usage: i32,
+}
+
+impl RandomInt {
+    pub fn new(seed: i64, usage_per_row: i32) -> Self {
+        Self {
+            seed,
+            usage_per_row,
+            usage: 0,
+        }
+    }
+
+    /// Return a value in the range `low..=high`. The range is computed with 32 bit arithmetic,
+    /// which overflows for `0..=i32::MAX` in the same way as dbgen, and `a_rnd` relies on that.
+    pub fn next_int(&mut self, low: i32, high: i32) -> i32 {
+        self.next_rand();
+        let range = high.wrapping_sub(low).wrapping_add(1) as f64;
+        let value = ((self.seed as f64 / MODULUS as f64) * range) as i32;
+        low.wrapping_add(value)
+    }
+
+    fn next_rand(&mut self) -> i64 {
+        debug_assert!(
+            self.usage < self.usage_per_row,
+            "random stream used more than {} times per row",
+            self.usage_per_row
+        );
+        self.seed = (self.seed * MULTIPLIER) % MODULUS;
+        self.usage += 1;
+        self.seed
+    }
+
+    /// Skip the remaining values reserved for the current row
+    pub fn row_finished(&mut self) {
+        self.advance_seed((self.usage_per_row - self.usage) as i64);
+        self.usage = 0;
+    }
+
+    /// Skip the values for `rows` rows, which is how dbgen generates a chunk of a table
+    pub fn advance_rows(&mut self, rows: i64) {
+        if self.usage != 0 {
+            self.row_finished();
+        }
+        self.advance_seed(self.usage_per_row as i64 * rows);
+    }
+
+    fn advance_seed(&mut self, mut count: i64) {
+        let mut multiplier = MULTIPLIER;
+        while count > 0 {
+            if count % 2 != 0 {
+                self.seed = (multiplier * self.seed) % MODULUS;
+            }
+            count /= 2;
+            multiplier = (multiplier * multiplier) % MODULUS;
+        }
+    }
+}
+
+const MULTIPLIER_64: i64 = 6364136223846793005;
+const INCREMENT_64: i64 = 1;
+
+/// 64 bit generator used for keys at scale factors where they no longer fit in 32 bits
+#[derive(Debug, Clone)]
+pub struct RandomLong {
+    seed: i64,
+    usage_per_row: i32,
+    usage: i32,
+}
+
+impl RandomLong {
+    pub fn new(seed: i64, usage_per_row: i32) -> Self {
+        Self {
+            seed,
+            usage_per_row,
+            usage: 0,
+        }
+    }
+
+    pub fn next_long(&mut self, low: i64, high: i64) -> i64 {
+        self.seed = self
+            .seed
+            .wrapping_mul(MULTIPLIER_64)
+            .wrapping_add(INCREMENT_64);
+        self.usage += 1;
+        low + self.seed.wrapping_abs() % (high - low + 1)
+    }
+
+    pub fn row_finished(&mut self) {
+        self.advance_seed((self.usage_per_row - self.usage) as i64);
+        self.usage = 0;
+    }
+
+    pub fn advance_rows(&mut self, rows: i64) {
+        if self.usage != 0 {
+            self.row_finished();
+        }
+        self.advance_seed(self.usage_per_row as i64 * rows);
+    }
+
+    fn advance_seed(&mut self, mut count: i64) {
+        let mut multiplier = MULTIPLIER_64;
+        let mut increment = INCREMENT_64;
+        let mut total_multiplier: i64 = 1;
+        let mut total_increment: i64 = 0;
+        while count > 0 {
+            if count % 2 != 0 {
+                total_multiplier = total_multiplier.wrapping_mul(multiplier);
+                total_increment = total_increment
+                    .wrapping_mul(multiplier)
+                    .wrapping_add(increment);
+            }
+            increment = multiplier.wrapping_add(1).wrapping_mul(increment);
+            multiplier = multiplier.wrapping_mul(multiplier);
+            count /= 2;
+        }
+        self.seed = self
+            .seed
+            .wrapping_mul(total_multiplier)
+            .wrapping_add(total_increment);
+    }
+}
+
+/// Integer column with values uniformly distributed in `low..=high`
+#[derive(Debug, Clone)]
+pub struct RandomBoundedInt {
+    random: RandomInt,
+    low: i32,
+    high: i32,
+}
+
+impl RandomBoundedInt {
+    pub fn new(seed: i64, low: i32, high: i32, expected_row_count: i32) -> Self {
+        Self {
+            random: RandomInt::new(seed, expected_row_count),
+            low,
+            high,
+        }
+    }
+
+    pub fn next_value(&mut self) -> i32 {
+        self.random.next_int(self.low, self.high)
+    }
+
+    pub fn row_finished(&mut self) {
+        self.random.row_finished()
+    }
+
+    pub fn advance_rows(&mut self, rows: i64) {
+        self.random.advance_rows(rows)
+    }
+}
+
+/// Key column that switches to the 64 bit generator for very large scale factors
+#[derive(Debug, Clone)]
+pub enum RandomBoundedLong {
+    Int(RandomInt, i32, i32),
+    Long(RandomLong, i64, i64),
+}
+
+impl RandomBoundedLong {
+    pub fn new(seed: i64, use_64_bits: bool, low: i64, high: i64, expected_row_count: i32) -> Self {
+        if use_64_bits {
+            RandomBoundedLong::Long(RandomLong::new(seed, expected_row_count), low, high)
+        } else {
+            RandomBoundedLong::Int(
+                RandomInt::new(seed, expected_row_count),
+                low as i32,
+                high as i32,
+            )
+        }
+    }
+
+    pub fn next_value(&mut self) -> i64 {
+        match self {
+            RandomBoundedLong::Int(random, low, high) => random.next_int(*low, *high) as i64,
+            RandomBoundedLong::Long(random, low, high) => random.next_long(*low, *high),
+        }
+    }
+
+    pub fn row_finished(&mut self) {
+        match self {
+            RandomBoundedLong::Int(random, _, _) => random.row_finished(),
+            RandomBoundedLong::Long(random, _, _) => random.row_finished(),
+        }
+    }
+
+    pub fn advance_rows(&mut self, rows: i64) {
+        match self {
+            RandomBoundedLong::Int(random, _, _) => random.advance_rows(rows),
+            RandomBoundedLong::Long(random, _, _) => random.advance_rows(rows),
+        }
+    }
+}
+
+/// Value picked from a weighted distribution, as implemented by `pick_str` in dbgen
+#[derive(Debug, Clone)]
+pub struct RandomString {
+    random: RandomInt,
+    distribution: Distribution,
+}
+
+impl RandomString {
+    pub fn new(seed: i64, distribution: Distribution, expected_row_count: i32) -> Self {
+        Self {
+            random: RandomInt::new(seed, expected_row_count),
+            distribution,
+        }
+    }
+
+    pub fn next_value(&mut self) -> &'static str {
+        self.distribution.random_value(&mut self.random)
+    }
+
+    pub fn row_finished(&mut self) {
+        self.random.row_finished()
+    }
+
+    pub fn advance_rows(&mut self, rows: i64) {
+        self.random.advance_rows(rows)
+    }
+}
+
+/// A sequence of distinct values from a distribution, as implemented by `agg_str` in dbgen. A
+/// permutation of the whole distribution is reserved per row even though only the first
+/// `count` values are used.
+#[derive(Debug, Clone)]
+pub struct RandomStringSequence {
+    random: RandomInt,
+    count: usize,
+    distribution: Distribution,
+}
+
+impl RandomStringSequence {
+    pub fn new(seed: i64, count: usize, distribution: Distribution) -> Self {
+        Self {
+            random: RandomInt::new(seed, distribution.size() as i32),
+            count,
+            distribution,
+        }
+    }
+
+    pub fn next_value(&mut self) -> String {
+        let mut values = self.distribution.values().to_vec();
+        for position in 0..self.count {
+            let swap = self
+                .random
+                .next_int(position as i32, values.len() as i32 - 1);
+            values.swap(position, swap as usize);
+        }
+        values[0..self.count].join(" ")
+    }
+
+    pub fn row_finished(&mut self) {
+        self.random.row_finished()
+    }
+
+    pub fn advance_rows(&mut self, rows: i64) {
+        self.random.advance_rows(rows)
+    }
+}
+
+/// Text of random length taken from a random offset in the text pool, as implemented by
+/// `dbg_text` in dbgen. The length varies between 40% and 160% of the average length.
+#[derive(Debug, Clone)]
+pub struct RandomText {
+    random: RandomInt,
+    pool: &'static TextPool,
+    min_length: i32,
+    max_length: i32,
+}
+
+impl RandomText {
+    pub fn new(
+        seed: i64,
+        pool: &'static TextPool,
+        average_length: f64,
+        expected_row_count: i32,
+    ) -> Self {
+        Self {
+            random: RandomInt::new(seed, 2 * expected_row_count),
+            pool,
+            min_length: (average_length * 0.4) as i32,
+            max_length: (average_length * 1.6) as i32,
+        }
+    }
+
+    pub fn next_value(&mut self) -> &'static str {
+        let offset = self
+            .random
+            .next_int(0, self.pool.size() as i32 - self.max_length);
+        let length = self.random.next_int(self.min_length, self.max_length);
+        self.pool.text(offset as usize, (offset + length) as usize)
+    }
+
+    pub fn row_finished(&mut self) {
+        self.random.row_finished()
+    }
+
+    pub fn advance_rows(&mut self, rows: i64) {
+        self.random.advance_rows(rows)
+    }
+}
+
+const ALPHA_NUMERIC: &[u8; 64] =
+    b"0123456789abcdefghijklmnopqrstuvwxyz ABCDEFGHIJKLMNOPQRSTUVWXYZ,";
+
+/// Random alphanumeric string, as implemented by `a_rnd` in dbgen. Each random value provides
+/// the characters for five positions.
+#[derive(Debug, Clone)]
+pub struct RandomAlphaNumeric {
+    random: RandomInt,
+    min_length: i32,
+    max_length: i32,
+}
+
+impl RandomAlphaNumeric {
+    pub fn new(seed: i64, average_length: f64) -> Self {
+        Self {
+            random: RandomInt::new(seed, 9),
+            min_length: (average_length * 0.4) as i32,
+            max_length: (average_length * 1.6) as i32,
+        }
+    }
+
+    pub fn next_value(&mut self) -> String {
+        let length = self.random.next_int(self.min_length, self.max_length) as usize;
+        let mut value = String::with_capacity(length);
+        let mut char_index: i64 = 0;
+        for i in 0..length {
+            if i % 5 == 0 {
+                char_index = self.random.next_int(0, i32::MAX) as i64;
+            }
+            value.push(ALPHA_NUMERIC[(char_index & 0x3f) as usize] as char);
+            char_index >>= 6;
+        }
+        value
+    }
+
+    pub fn row_finished(&mut self) {
+        self.random.row_finished()
+    }
+
+    pub fn advance_rows(&mut self, rows: i64) {
+        self.random.advance_rows(rows)
+    }
+}
+
+/// Phone number with a country code derived from the nation key
+#[derive(Debug, Clone)]
+pub struct RandomPhoneNumber {
+    random: RandomInt,
+}
+
+impl RandomPhoneNumber {
+    pub fn new(seed: i64) -> Self {
+        Self {
+            random: RandomInt::new(seed, 3),
+        }
+    }
+
+    pub fn next_value(&mut self, nation_key: i64) -> String {
+        format!(
+            "{:02}-{:03}-{:03}-{:04}",
+            10 + nation_key % 90,
+            self.random.next_int(100, 999),
+            self.random.next_int(100, 999),
+            self.random.next_int(1000, 9999)
+        )
+    }
+
+    pub fn row_finished(&mut self) {
+        self.random.row_finished()
+    }
+
+    pub fn advance_rows(&mut self, rows: i64) {
+        self.random.advance_rows(rows)
+    }
+}
@@ -0,0 +1,17 @@
+#!/bin/sh
+# Write the reference output of dbgen that the native generator is compared with in full by the
+# ignored tests in src/tpch/mod.rs, then run them with `cargo test -- --ignored`.
+#
+# usage: dbgen.sh <tpch-dbgen directory>
+set -e
+
+DBGEN_DIR=$(cd "$1" && pwd)
+OUTPUT_DIR=$(cd "$(dirname "$0")" && pwd)/dbgen
+
+# a fractional scale factor in a single chunk
+mkdir -p "$OUTPUT_DIR/sf0.01"
+(cd "$DBGEN_DIR" && DSS_PATH="$OUTPUT_DIR/sf0.01" ./dbgen -f -q -s 0.01)
+
+# a chunk other than the first of a multi-chunk run
+mkdir -p "$OUTPUT_DIR/sf0.01-c4-s3"
+(cd "$DBGEN_DIR" && DSS_PATH="$OUTPUT_DIR/sf0.01-c4-s3" ./dbgen -f -q -s 0.01 -C 4 -S 3)
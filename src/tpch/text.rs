@@ -0,0 +1,149 @@
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::sync::OnceLock;
+
+use super::distribution::*;
+use super::random::RandomInt;
+
+/// Size of the text pool generated by dbgen, which all comments are taken from
+const TEXT_POOL_SIZE: usize = 300 * 1024 * 1024;
+
+/// Seed of the random stream used to generate the text pool
+const TEXT_POOL_SEED: i64 = 933588178;
+
+static TEXT_POOL: OnceLock<TextPool> = OnceLock::new();
+
+/// Pseudo-English text generated from the grammar in `dists.dss`. Comment columns are random
+/// substrings of this text.
+#[derive(Debug)]
+pub struct TextPool {
+    text: String,
+}
+
+impl TextPool {
+    /// Get the shared text pool, generating it on first use
+    pub fn get() -> &'static TextPool {
+        TEXT_POOL.get_or_init(|| TextPool::generate(TEXT_POOL_SIZE))
+    }
+
+    fn generate(size: usize) -> Self {
+        let grammar = Grammar::new();
+        // the stream is never advanced to the next row
+        let mut random = RandomInt::new(TEXT_POOL_SEED, i32::MAX);
+        let mut text = String::with_capacity(size + 256);
+        while text.len() < size {
+            grammar.sentence(&mut text, &mut random);
+        }
+        text.truncate(size);
+        Self { text }
+    }
+
+    pub fn size(&self) -> usize {
+        self.text.len()
+    }
+
+    pub fn text(&self, start: usize, end: usize) -> &str {
+        &self.text[start..end]
+    }
+}
+
+struct Grammar {
+    grammar: Distribution,
+    noun_phrases: Distribution,
+    verb_phrases: Distribution,
+    nouns: Distribution,
+    verbs: Distribution,
+    adjectives: Distribution,
+    adverbs: Distribution,
+    articles: Distribution,
+    prepositions: Distribution,
+    auxiliaries: Distribution,
+    terminators: Distribution,
+}
+
+impl Grammar {
+    fn new() -> Self {
+        Self {
+            grammar: Distribution::new(GRAMMAR),
+            noun_phrases: Distribution::new(NOUN_PHRASES),
+            verb_phrases: Distribution::new(VERB_PHRASES),
+            nouns: Distribution::new(NOUNS),
+            verbs: Distribution::new(VERBS),
+            adjectives: Distribution::new(ADJECTIVES),
+            adverbs: Distribution::new(ADVERBS),
+            articles: Distribution::new(ARTICLES),
+            prepositions: Distribution::new(PREPOSITIONS),
+            auxiliaries: Distribution::new(AUXILIARIES),
+            terminators: Distribution::new(TERMINATORS),
+        }
+    }
+
+    fn sentence(&self, text: &mut String, random: &mut RandomInt) {
+        let syntax = self.grammar.random_value(random);
+        for token in syntax.split(' ') {
+            match token {
+                "V" => self.verb_phrase(text, random),
+                "N" => self.noun_phrase(text, random),
+                "P" => {
+                    text.push_str(self.prepositions.random_value(random));
+                    text.push_str(" the ");
+                    self.noun_phrase(text, random);
+                }
+                "T" => {
+                    // terminators abut the previous word
+                    text.pop();
+                    text.push_str(self.terminators.random_value(random));
+                }
+                other => unreachable!("unknown sentence token '{}'", other),
+            }
+            if !text.ends_with(' ') {
+                text.push(' ');
+            }
+        }
+    }
+
+    fn verb_phrase(&self, text: &mut String, random: &mut RandomInt) {
+        let syntax = self.verb_phrases.random_value(random);
+        for token in syntax.split(' ') {
+            let words = match token {
+                "D" => &self.adverbs,
+                "V" => &self.verbs,
+                "X" => &self.auxiliaries,
+                other => unreachable!("unknown verb phrase token '{}'", other),
+            };
+            text.push_str(words.random_value(random));
+            text.push(' ');
+        }
+    }
+
+    fn noun_phrase(&self, text: &mut String, random: &mut RandomInt) {
+        let syntax = self.noun_phrases.random_value(random);
+        for token in syntax.chars() {
+            let words = match token {
+                'A' => &self.articles,
+                'J' => &self.adjectives,
+                'D' => &self.adverbs,
+                'N' => &self.nouns,
+                ',' => {
+                    text.pop();
+                    text.push_str(", ");
+                    continue;
+                }
+                ' ' => continue,
+                other => unreachable!("unknown noun phrase token '{}'", other),
+            };
+            text.push_str(words.random_value(random));
+            text.push(' ');
+        }
+    }
+}
@@ -0,0 +1,283 @@
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::cmp::Ordering;
+use std::fs;
+use std::path::Path;
+
+use datafusion::arrow::datatypes::DataType;
+use datafusion::arrow::record_batch::RecordBatch;
+use datafusion::arrow::util::display::array_value_to_string;
+use datafusion::error::Result;
+
+/// Maximum number of differences to report per query
+const MAX_DIFFERENCES: usize = 10;
+
+/// Decimal and floating point values, such as sums of money and averages, may differ by up to
+/// one cent, or by up to 1% of the expected value, which is the tolerance the TPC-H specification
+/// allows for averages and ratios. All other values must match exactly.
+const ABSOLUTE_TOLERANCE: f64 = 0.01;
+const RELATIVE_TOLERANCE: f64 = 0.01;
+
+/// Expected query results, as found in the TPC-H `answers/qN.out` files
+#[derive(Debug)]
+pub struct AnswerSet {
+    pub columns: Vec<String>,
+    pub rows: Vec<Vec<String>>,
+}
+
+/// Load a pipe-delimited reference answer file. The first line contains the column names and
+/// any padding around values is ignored.
+pub fn load_answers(path: &Path) -> Result<AnswerSet> {
+    let contents = fs::read_to_string(path)?;
+    let mut lines = contents.lines().filter(|l| !l.trim().is_empty());
+    let columns = lines.next().map(split_line).unwrap_or_default();
+    let rows = lines.map(split_line).collect();
+    Ok(AnswerSet { columns, rows })
+}
+
+fn split_line(line: &str) -> Vec<String> {
+    let line = line.trim_end();
+    let line = line.strip_suffix('|').unwrap_or(line);
+    line.split('|').map(|s| s.trim().to_owned()).collect()
+}
+
+/// Compare query results against the reference answers, returning a description of each
+/// difference found. When `ordered` is false the rows are sorted before comparing them.
+pub fn compare_results(
+    batches: &[RecordBatch],
+    expected: &AnswerSet,
+    ordered: bool,
+) -> Result<Vec<String>> {
+    let approximate: Vec<bool> = match batches.first() {
+        Some(batch) => batch
+            .schema()
+            .fields()
+            .iter()
+            .map(|f| is_approximate(f.data_type()))
+            .collect(),
+        None => vec![],
+    };
+    let mut actual = vec![];
+    for batch in batches {
+        for row in 0..batch.num_rows() {
+            let mut values = vec![];
+            for column in batch.columns() {
+                values.push(array_value_to_string(column, row)?.trim().to_owned());
+            }
+            actual.push(values);
+        }
+    }
+    let mut expected_rows = expected.rows.clone();
+    if !ordered {
+        actual.sort_by(|a, b| compare_rows(a, b));
+        expected_rows.sort_by(|a, b| compare_rows(a, b));
+    }
+
+    let mut differences = vec![];
+    if actual.len() != expected_rows.len() {
+        differences.push(format!(
+            "expected {} rows but got {}",
+            expected_rows.len(),
+            actual.len()
+        ));
+    }
+    for (i, (actual_row, expected_row)) in actual.iter().zip(&expected_rows).enumerate() {
+        if actual_row.len() != expected_row.len() {
+            differences.push(format!(
+                "row {}: expected {} columns but got {}",
+                i,
+                expected_row.len(),
+                actual_row.len()
+            ));
+            continue;
+        }
+        for (j, (a, e)) in actual_row.iter().zip(expected_row).enumerate() {
+            let approximate = approximate.get(j).copied().unwrap_or(false);
+            if !values_match(a, e, approximate) {
+                let column = expected.columns.get(j).map(|s| s.as_str()).unwrap_or("?");
+                differences.push(format!(
+                    "row {} column {}: expected '{}' but got '{}'",
+                    i, column, e, a
+                ));
+            }
+        }
+    }
+
+    if differences.len() > MAX_DIFFERENCES {
+        let more = differences.len() - MAX_DIFFERENCES;
+        differences.truncate(MAX_DIFFERENCES);
+        differences.push(format!("... and {} more differences", more));
+    }
+    Ok(differences)
+}
+
+/// Whether values of the type are compared within tolerance rather than exactly
+fn is_approximate(data_type: &DataType) -> bool {
+    matches!(
+        data_type,
+        DataType::Decimal128(_, _)
+            | DataType::Decimal256(_, _)
+            | DataType::Float16
+            | DataType::Float32
+            | DataType::Float64
+    )
+}
+
+fn values_match(actual: &str, expected: &str, approximate: bool) -> bool {
+    if actual == expected {
+        return true;
+    }
+    if !approximate {
+        return false;
+    }
+    match (actual.parse::<f64>(), expected.parse::<f64>()) {
+        (Ok(a), Ok(e)) => {
+            // allow for the representation error of the parsed values
+            let diff = (a - e).abs() - 1e-9 * e.abs().max(1.0);
+            diff <= ABSOLUTE_TOLERANCE || diff <= RELATIVE_TOLERANCE * e.abs()
+        }
+        _ => false,
+    }
+}
+
+/// Order rows by their values, comparing numbers by value and everything else as text
+fn compare_rows(a: &[String], b: &[String]) -> Ordering {
+    for (a, b) in a.iter().zip(b) {
+        let ordering = match (a.parse::<f64>(), b.parse::<f64>()) {
+            (Ok(a), Ok(b)) => a.total_cmp(&b),
+            _ => a.cmp(b),
+        };
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+    a.len().cmp(&b.len())
+}
+
+/// Determine whether the final statement of a query imposes an ordering on its results
+pub fn has_order_by(sql: &str) -> bool {
+    let normalized = sql
+        .lines()
+        .filter(|l| !l.trim_start().starts_with("--"))
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase();
+    normalized
+        .split(';')
+        .map(|s| s.trim())
+        .rfind(|s| s.starts_with("select") || s.starts_with("with"))
+        .map(|s| s.split_whitespace().collect::<Vec<_>>().join(" "))
+        .map(|s| s.contains("order by"))
+        .unwrap_or(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use datafusion::arrow::array::{Decimal128Array, Int64Array, StringArray};
+    use datafusion::arrow::datatypes::{Field, Schema};
+    use std::sync::Arc;
+
+    fn answers(rows: &[&[&str]]) -> AnswerSet {
+        AnswerSet {
+            columns: vec!["name".to_owned(), "count".to_owned(), "total".to_owned()],
+            rows: rows
+                .iter()
+                .map(|r| r.iter().map(|v| v.to_string()).collect())
+                .collect(),
+        }
+    }
+
+    fn batch(names: &[&str], counts: &[i64], totals: &[i128]) -> RecordBatch {
+        let schema = Schema::new(vec![
+            Field::new("name", DataType::Utf8, false),
+            Field::new("count", DataType::Int64, false),
+            Field::new("total", DataType::Decimal128(15, 2), false),
+        ]);
+        let totals = Decimal128Array::from(totals.to_vec())
+            .with_precision_and_scale(15, 2)
+            .unwrap();
+        RecordBatch::try_new(
+            Arc::new(schema),
+            vec![
+                Arc::new(StringArray::from(names.to_vec())),
+                Arc::new(Int64Array::from(counts.to_vec())),
+                Arc::new(totals),
+            ],
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn load_answer_file() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("q1.out");
+        fs::write(&path, "name |count|total\nBRAZIL   |  3|  10.50|\n\n")?;
+        let answers = load_answers(&path)?;
+        assert_eq!(vec!["name", "count", "total"], answers.columns);
+        assert_eq!(vec![vec!["BRAZIL", "3", "10.50"]], answers.rows);
+        Ok(())
+    }
+
+    #[test]
+    fn decimals_match_within_tolerance() -> Result<()> {
+        let actual = batch(&["BRAZIL"], &[3], &[1_000_001]);
+        let differences =
+            compare_results(&[actual], &answers(&[&["BRAZIL", "3", "10000.00"]]), true)?;
+        assert!(differences.is_empty(), "{:?}", differences);
+
+        let actual = batch(&["BRAZIL"], &[3], &[1_200_000]);
+        let differences =
+            compare_results(&[actual], &answers(&[&["BRAZIL", "3", "10000.00"]]), true)?;
+        assert_eq!(1, differences.len());
+        Ok(())
+    }
+
+    #[test]
+    fn integers_and_strings_match_exactly() -> Result<()> {
+        // 1000 and 1001 are within 1% of each other but counts must be exact
+        let actual = batch(&["BRAZIL", "brazil"], &[1001, 3], &[100, 100]);
+        let expected = answers(&[&["BRAZIL", "1000", "1.00"], &["BRAZIL", "3", "1.00"]]);
+        let differences = compare_results(&[actual], &expected, true)?;
+        assert_eq!(
+            vec![
+                "row 0 column count: expected '1000' but got '1001'",
+                "row 1 column name: expected 'BRAZIL' but got 'brazil'",
+            ],
+            differences
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn unordered_results_sort_on_exact_values() -> Result<()> {
+        // the totals round to the same cent, so only the exact values tell the rows apart
+        let actual = batch(&["A", "B"], &[1, 2], &[1_001, 1_000]);
+        let expected = answers(&[&["B", "2", "10.00"], &["A", "1", "10.01"]]);
+        assert!(compare_results(&[actual], &expected, false)?.is_empty());
+        // numbers sort by value rather than as text
+        let actual = batch(&["A", "B"], &[10, 9], &[100, 100]);
+        let expected = answers(&[&["B", "9", "1.00"], &["A", "10", "1.00"]]);
+        assert!(compare_results(&[actual], &expected, false)?.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn detect_order_by() {
+        assert!(has_order_by("select a from t\norder by a;"));
+        assert!(!has_order_by("select a from t\n-- order by a\n;"));
+        assert!(!has_order_by(
+            "create view v as select a from t order by a;\nselect a from v;"
+        ));
+    }
+}
@@ -0,0 +1,142 @@
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::fs::{self, File};
+use std::io::{BufReader, Read};
+use std::path::Path;
+
+use datafusion::error::Result;
+use datafusion::parquet::file::reader::{FileReader, SerializedFileReader};
+
+use crate::manifest::Manifest;
+use crate::{Format, Tpc};
+
+/// Number of rows found for a table, the number expected by the specification if known, and the
+/// number recorded in the manifest of the data set if it has one
+#[derive(Debug, Clone)]
+pub struct RowCount {
+    pub table: String,
+    pub expected: Option<u64>,
+    pub actual: u64,
+    pub manifest: Option<u64>,
+}
+
+impl RowCount {
+    pub fn is_valid(&self) -> bool {
+        self.expected.map(|e| e == self.actual).unwrap_or(true) && !self.has_drift()
+    }
+
+    /// Whether the files no longer contain the number of rows recorded in the manifest
+    pub fn has_drift(&self) -> bool {
+        self.manifest.map(|m| m != self.actual).unwrap_or(false)
+    }
+}
+
+/// Count the rows of every table in a data set and compare them with the row counts defined by
+/// the specification for the scale factor. Parquet row counts are read from the file metadata
+/// and raw files are counted by line. The files are always counted, the row counts in the
+/// manifest are only used to detect data that changed after the manifest was written.
+pub fn verify_row_counts(
+    benchmark: &dyn Tpc,
+    data_path: &str,
+    format: Format,
+    scale: f64,
+) -> Result<Vec<RowCount>> {
+    let manifest = Manifest::read(data_path)?.filter(|m| m.format == format.to_string());
+    let mut counts = vec![];
+    for table in benchmark.get_table_names() {
+        let path = format.table_path(benchmark, data_path, table);
+        let path = Path::new(&path);
+        let actual = if path.exists() {
+            count_rows(path, format)?
+        } else {
+            // a missing table is reported as empty rather than failing the whole check
+            0
+        };
+        counts.push(RowCount {
+            table: table.to_owned(),
+            expected: benchmark.get_row_count(table, scale),
+            actual,
+            manifest: manifest
+                .as_ref()
+                .and_then(|m| m.table(table))
+                .and_then(|t| t.rows),
+        });
+    }
+    Ok(counts)
+}
+
+/// Count the rows in a file, or in all files below a directory
+pub(crate) fn count_rows(path: &Path, format: Format) -> Result<u64> {
+    if path.is_dir() {
+        let mut count = 0;
+        for entry in fs::read_dir(path)? {
+            count += count_rows(&entry?.path(), format)?;
+        }
+        return Ok(count);
+    }
+    match format {
+        Format::Parquet => {
+            if path.extension().and_then(|e| e.to_str()) != Some("parquet") {
+                return Ok(0);
+            }
+            let reader = SerializedFileReader::new(File::open(path)?)?;
+            Ok(reader.metadata().file_metadata().num_rows() as u64)
+        }
+        Format::Raw => {
+            let mut reader = BufReader::new(File::open(path)?);
+            let mut buf = vec![0; 1024 * 1024];
+            let mut count = 0;
+            loop {
+                let n = reader.read(&mut buf)?;
+                if n == 0 {
+                    break;
+                }
+                count += buf[..n].iter().filter(|b| **b == b'\n').count() as u64;
+            }
+            Ok(count)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row_count(expected: Option<u64>, actual: u64, manifest: Option<u64>) -> RowCount {
+        RowCount {
+            table: "nation".to_owned(),
+            expected,
+            actual,
+            manifest,
+        }
+    }
+
+    #[test]
+    fn manifest_drift_is_invalid() {
+        assert!(row_count(Some(25), 25, Some(25)).is_valid());
+        assert!(row_count(None, 25, None).is_valid());
+        assert!(!row_count(Some(25), 24, None).is_valid());
+        let drifted = row_count(Some(25), 25, Some(24));
+        assert!(drifted.has_drift());
+        assert!(!drifted.is_valid());
+    }
+
+    #[test]
+    fn count_raw_rows_in_directory() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        fs::write(dir.path().join("part-0.tbl"), "1|a|\n2|b|\n")?;
+        fs::write(dir.path().join("part-1.tbl"), "3|c|\n")?;
+        assert_eq!(3, count_rows(dir.path(), Format::Raw)?);
+        Ok(())
+    }
+}